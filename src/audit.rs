@@ -0,0 +1,165 @@
+//! Compact record of every command posted to an audit channel, so there is
+//! always an answer to "who stopped the server?"
+use crate::config::Verb;
+use crate::{commands, tokens};
+
+use serenity::all::CommandInteraction;
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::http::Http;
+use serenity::model::Colour;
+use serenity::model::Timestamp;
+use serenity::model::application::{ResolvedOption, ResolvedValue};
+use serenity::model::user::User;
+
+use std::time::Duration;
+
+/// Discord refuses embed fields longer than this
+const FIELD_LIMIT: usize = 1024;
+
+/// How a command invocation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The command ran, whether or not the operation itself worked
+    Completed,
+    /// Players were online, so the member was asked to confirm first and
+    /// nothing has run yet
+    AwaitingConfirmation,
+    /// The member was not allowed to run it
+    Denied,
+}
+
+/// Who did what, from a slash command or a button
+pub struct Entry {
+    user: User,
+    /// e.g. `/stop game:skyvaults`
+    invocation: String,
+    game: Option<String>,
+}
+
+impl Entry {
+    pub fn command(command: &CommandInteraction) -> Self {
+        let options = command.data.options();
+        Entry {
+            user: command.user.clone(),
+            invocation: describe(&command.data.name, &options),
+            game: commands::find_game(&options).map(str::to_string),
+        }
+    }
+
+    pub fn button(user: &User, verb: Verb, game: &str) -> Self {
+        Entry {
+            user: user.clone(),
+            invocation: format!("{} button for {game}", verb.name()),
+            game: Some(game.to_string()),
+        }
+    }
+}
+
+/// Log an invocation and post it to the audit channel, if there is one
+pub async fn record(http: &Http, entry: &Entry, outcome: Outcome, result: &str, elapsed: Duration) {
+    let Entry {
+        user,
+        invocation,
+        game,
+    } = entry;
+
+    println!(
+        "[audit] {} ran `{invocation}`: {outcome:?} after {:.1}s",
+        user.name,
+        elapsed.as_secs_f32()
+    );
+
+    let Some(channel) = *tokens::AUDIT_CHANNEL_ID else {
+        return;
+    };
+
+    let colour = match outcome {
+        Outcome::Completed => Colour::BLURPLE,
+        Outcome::AwaitingConfirmation => Colour::GOLD,
+        Outcome::Denied => Colour::RED,
+    };
+
+    let embed = CreateEmbed::new()
+        .title(format!("`{invocation}`"))
+        .colour(colour)
+        .field("User", format!("<@{}> ({})", user.id, user.name), true)
+        .field("Game", game.as_deref().unwrap_or("-"), true)
+        .field("Elapsed", format!("{:.1}s", elapsed.as_secs_f32()), true)
+        .field("Result", truncate(result, FIELD_LIMIT), false)
+        .timestamp(Timestamp::now());
+
+    if let Err(e) = channel
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await
+    {
+        eprintln!("Failed to post to the audit channel: {e}");
+    }
+}
+
+/// The command as it would be typed, e.g. `/backup restore game:enshrouded id:...`
+fn describe(name: &str, options: &[ResolvedOption<'_>]) -> String {
+    let mut invocation = format!("/{name}");
+    push_options(&mut invocation, options);
+    invocation
+}
+
+fn push_options(invocation: &mut String, options: &[ResolvedOption<'_>]) {
+    for option in options {
+        match &option.value {
+            ResolvedValue::SubCommand(inner) | ResolvedValue::SubCommandGroup(inner) => {
+                invocation.push_str(&format!(" {}", option.name));
+                push_options(invocation, inner);
+            }
+            ResolvedValue::String(value) => {
+                invocation.push_str(&format!(" {}:{value}", option.name))
+            }
+            ResolvedValue::Integer(value) => {
+                invocation.push_str(&format!(" {}:{value}", option.name))
+            }
+            ResolvedValue::Boolean(value) => {
+                invocation.push_str(&format!(" {}:{value}", option.name))
+            }
+            _ => invocation.push_str(&format!(" {}:...", option.name)),
+        }
+    }
+}
+
+/// Cut `text` down to `limit` bytes on a character boundary
+fn truncate(text: &str, limit: usize) -> String {
+    const ELLIPSIS: &str = "...";
+
+    let text = text.trim();
+    if text.is_empty() {
+        return "-".to_string();
+    }
+    if text.len() <= limit {
+        return text.to_string();
+    }
+
+    let mut end = limit - ELLIPSIS.len();
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{ELLIPSIS}", &text[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_results_are_cut_on_a_character_boundary() {
+        let result = "é".repeat(600);
+
+        let truncated = truncate(&result, FIELD_LIMIT);
+
+        assert!(truncated.len() <= FIELD_LIMIT);
+        assert!(truncated.ends_with("é..."));
+    }
+
+    #[test]
+    fn short_and_empty_results_are_kept_readable() {
+        assert_eq!(truncate(" stopped \n", FIELD_LIMIT), "stopped");
+        assert_eq!(truncate("", FIELD_LIMIT), "-");
+    }
+}
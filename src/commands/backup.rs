@@ -0,0 +1,122 @@
+use crate::games::{self, BackupInfo};
+
+use chrono::{DateTime, Local};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+/// Backups shown by `/backup list`, the rest are only counted
+const LIST_LIMIT: usize = 15;
+
+pub fn register() -> CreateCommand {
+    let game = || {
+        let mut option =
+            CreateCommandOption::new(CommandOptionType::String, "game", "Name of the game server")
+                .required(true);
+
+        for game in games::all().iter() {
+            option = option.add_string_choice(game.name(), game.name())
+        }
+        option
+    };
+
+    let id = CreateCommandOption::new(CommandOptionType::String, "id", "Backup id from list")
+        .required(true);
+
+    CreateCommand::new("backup")
+        .description("Back up and restore game saves")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List backups")
+                .add_sub_option(game()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "create", "Back up now")
+                .add_sub_option(game()),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "restore",
+                "Stop the server and restore a backup",
+            )
+            .add_sub_option(game())
+            .add_sub_option(id),
+        )
+}
+
+pub async fn run(options: &[ResolvedOption<'_>], user: &str) -> String {
+    let Some(ResolvedOption {
+        name: subcommand,
+        value: ResolvedValue::SubCommand(options),
+        ..
+    }) = options.first()
+    else {
+        return "Please choose list, create or restore".to_string();
+    };
+
+    let option = |name: &str| {
+        options.iter().find_map(|option| match option.value {
+            ResolvedValue::String(value) if option.name == name => Some(value),
+            _ => None,
+        })
+    };
+
+    let Some(server) = option("game").and_then(games::get_game_server) else {
+        return "Please provide a valid game name".to_string();
+    };
+
+    match (*subcommand, option("id")) {
+        ("list", _) => match server.backups().await {
+            Ok(backups) => list(server.name(), &backups),
+            Err(error) => super::report(server, "list backups", Err(error)),
+        },
+        ("create", _) => super::run_operation(server, "back up", user, server.backup()).await,
+        ("restore", Some(id)) => {
+            super::run_operation(server, "restore", user, server.restore(id)).await
+        }
+        _ => "Please choose list, create or restore".to_string(),
+    }
+}
+
+fn list(name: &str, backups: &[BackupInfo]) -> String {
+    if backups.is_empty() {
+        return format!("The {name} server has no backups yet");
+    }
+
+    let mut message = format!("## Backups of {name}\n");
+    for backup in backups.iter().take(LIST_LIMIT) {
+        let created: DateTime<Local> = backup.created.into();
+        message.push_str(&format!(
+            "- `{}` ({}, {})\n",
+            backup.id,
+            format_size(backup.size),
+            created.format("%a %d %b %H:%M")
+        ));
+    }
+
+    if backups.len() > LIST_LIMIT {
+        message.push_str(&format!("...and {} older\n", backups.len() - LIST_LIMIT));
+    }
+
+    message
+}
+
+/// e.g. "1.5 GB"
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+
+    if unit == "B" {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {unit}")
+    }
+}
@@ -0,0 +1,143 @@
+use crate::games::{self, UpdateCheck};
+
+use serenity::builder::CreateCommand;
+
+/// One server's check, or why it could not be checked
+pub struct Row {
+    pub game: String,
+    pub check: Result<UpdateCheck, String>,
+}
+
+impl Row {
+    /// Identifies the pending update, so it is only announced once
+    pub fn pending_key(&self) -> Option<String> {
+        let check = self.check.as_ref().ok().filter(|check| check.pending)?;
+        let available = check.available.as_deref().unwrap_or("?");
+        Some(format!("{}@{available}", self.game))
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("checkupdates").description("Which servers have an update waiting")
+}
+
+pub async fn run() -> String {
+    table(&check_all().await)
+}
+
+/// Check every server at once, since steamcmd is slow to log in
+pub async fn check_all() -> Vec<Row> {
+    let checks: Vec<_> = games::all()
+        .iter()
+        .map(|server| (server.name(), tokio::spawn(server.check_update())))
+        .collect();
+
+    let mut rows = Vec::new();
+    for (game, check) in checks {
+        let check = match check.await {
+            Ok(check) => check.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        rows.push(Row {
+            game: game.to_string(),
+            check,
+        });
+    }
+    rows
+}
+
+/// A heading, then every server's versions lined up in a code block
+pub fn table(rows: &[Row]) -> String {
+    let pending = rows
+        .iter()
+        .filter(|row| row.pending_key().is_some())
+        .count();
+    let heading = match pending {
+        0 => "Everything is up to date".to_string(),
+        1 => "1 server has an update waiting".to_string(),
+        _ => format!("{pending} servers have an update waiting"),
+    };
+
+    let cells: Vec<[String; 4]> = rows
+        .iter()
+        .map(|row| match &row.check {
+            Ok(check) => [
+                row.game.clone(),
+                check.installed.clone().unwrap_or_else(|| "-".to_string()),
+                check.available.clone().unwrap_or_else(|| "-".to_string()),
+                if check.pending { "update waiting" } else { "" }.to_string(),
+            ],
+            Err(reason) => [row.game.clone(), "-".into(), "-".into(), reason.clone()],
+        })
+        .collect();
+
+    let header = ["Server", "Installed", "Available", ""].map(str::to_string);
+    let widths: Vec<usize> = (0..3)
+        .map(|column| {
+            std::iter::once(&header)
+                .chain(&cells)
+                .map(|cells| cells[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let lines: String = std::iter::once(&header)
+        .chain(&cells)
+        .map(|[game, installed, available, note]| {
+            let line = format!(
+                "{game:<0$}  {installed:<1$}  {available:<2$}  {note}",
+                widths[0], widths[1], widths[2]
+            );
+            format!("{}\n", line.trim_end())
+        })
+        .collect();
+
+    format!("**{heading}**\n```\n{lines}```")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(game: &str, installed: &str, available: &str) -> Row {
+        Row {
+            game: game.to_string(),
+            check: Ok(UpdateCheck::compare(
+                Some(installed.to_string()),
+                available.to_string(),
+            )),
+        }
+    }
+
+    #[test]
+    fn table_lines_up_every_server() {
+        let rows = [
+            row("7days", "1234567", "1239999"),
+            row("enshrouded", "16410553", "16410553"),
+            Row {
+                game: "zomboid".to_string(),
+                check: Err("can not check for updates".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            table(&rows),
+            "**1 server has an update waiting**\n```\n\
+             Server      Installed  Available\n\
+             7days       1234567    1239999    update waiting\n\
+             enshrouded  16410553   16410553\n\
+             zomboid     -          -          can not check for updates\n\
+             ```"
+        );
+    }
+
+    #[test]
+    fn pending_updates_are_keyed_by_version() {
+        assert_eq!(
+            row("7days", "1", "2").pending_key().as_deref(),
+            Some("7days@2")
+        );
+        assert_eq!(row("7days", "2", "2").pending_key(), None);
+    }
+}
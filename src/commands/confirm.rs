@@ -0,0 +1,214 @@
+//! Asking before a stop, restart or update kicks players, then giving them
+//! time to log off once the member confirms
+use crate::config::Verb;
+use crate::games::{self, GameServer, GameServerError};
+use crate::query::ServerInfo;
+
+use serenity::builder::{CreateActionRow, CreateButton};
+use serenity::model::application::ButtonStyle;
+
+use std::time::Duration;
+
+/// Start of every confirmation button id, followed by the verb and server name
+const BUTTON_PREFIX: &str = "confirm";
+
+/// Verbs that take the server down, so need confirming while anyone is on
+pub const KICKING_VERBS: [Verb; 3] = [Verb::Stop, Verb::Restart, Verb::Update];
+
+/// Seconds before the operation that players are warned, in-game where the
+/// server can broadcast
+pub const COUNTDOWN: [u64; 3] = [60, 30, 10];
+
+/// An ephemeral question for the member, with a button to go ahead
+pub struct Confirmation {
+    pub content: String,
+    pub button: CreateActionRow,
+}
+
+/// A confirmation if the verb would kick anyone, or nothing if it is safe
+/// to go ahead. Servers without a query can not tell, so never ask.
+pub async fn ask(verb: Verb, game: &str) -> Option<Confirmation> {
+    confirmation(verb, games::get_game_server(game)?).await
+}
+
+/// Like [ask], but also nothing when the verb would be refused anyway, so
+/// players are never warned about an update that can not happen
+async fn confirmation(verb: Verb, server: &dyn GameServer) -> Option<Confirmation> {
+    if !KICKING_VERBS.contains(&verb) {
+        return None;
+    }
+
+    if verb == Verb::Update && server.can_update().await.is_err() {
+        return None;
+    }
+
+    let game = server.name();
+    let info = server.status().await.info.filter(|info| info.players > 0)?;
+
+    let button = CreateButton::new(button_id(verb, game))
+        .label(format!("{} anyway", capitalise(verb.name())))
+        .style(ButtonStyle::Danger);
+
+    Some(Confirmation {
+        content: prompt(verb, game, &info),
+        button: CreateActionRow::Buttons(vec![button]),
+    })
+}
+
+/// e.g. "2 players are online on 7days (alice, bob). They will get 60
+/// seconds to log off. Stop anyway?"
+fn prompt(verb: Verb, game: &str, info: &ServerInfo) -> String {
+    let mut prompt = format!("{} on {game}", GameServerError::PlayersOnline(info.players));
+    if !info.player_names.is_empty() {
+        prompt.push_str(&format!(" ({})", info.player_names.join(", ")));
+    }
+
+    format!(
+        "{}. They will get {} seconds to log off. {} anyway?",
+        capitalise(&prompt),
+        COUNTDOWN[0],
+        capitalise(verb.name())
+    )
+}
+
+fn capitalise(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// e.g. `confirm:stop:7days`
+fn button_id(verb: Verb, game: &str) -> String {
+    format!("{BUTTON_PREFIX}:{}:{game}", verb.name())
+}
+
+/// The verb and server name of a button from [ask], if it is one
+pub fn parse_button(id: &str) -> Option<(Verb, &str)> {
+    let mut parts = id.splitn(3, ':');
+    if parts.next()? != BUTTON_PREFIX {
+        return None;
+    }
+
+    let verb = Verb::from_name(parts.next()?).filter(|verb| KICKING_VERBS.contains(verb))?;
+    let game = parts.next().filter(|game| !game.is_empty())?;
+    Some((verb, game))
+}
+
+/// Warn the players, count down, then run the verb on behalf of `user`
+pub async fn run(verb: Verb, game: &str, user: &str) -> String {
+    let Some(server) = games::get_game_server(game) else {
+        return format!("There is no {game} server any more");
+    };
+
+    // inside the operation, so nothing else can start during the countdown
+    let operation = async {
+        countdown(server, verb).await;
+        match verb {
+            Verb::Stop => server.stop().await,
+            Verb::Restart => server.restart().await,
+            _ => server.update().await,
+        }
+    };
+
+    super::run_operation(server, verb.name(), user, operation).await
+}
+
+/// Wait out [COUNTDOWN], broadcasting each step while the server can. The
+/// wait is kept without a broadcast, so players still get the time promised.
+async fn countdown(server: &dyn GameServer, verb: Verb) {
+    let mut broadcasting = true;
+    for (step, seconds) in COUNTDOWN.iter().enumerate() {
+        if broadcasting {
+            let message = format!("Server {} in {seconds} seconds", verb.name());
+            match server.broadcast(&message).await {
+                Ok(()) => {}
+                Err(GameServerError::NotSupported(_)) => broadcasting = false,
+                Err(error) => {
+                    eprintln!("{} broadcast failed: {}", server.name(), error.details());
+                    broadcasting = false;
+                }
+            }
+        }
+
+        let next = COUNTDOWN.get(step + 1).copied().unwrap_or(0);
+        tokio::time::sleep(Duration::from_secs(seconds - next)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ServerConfig, ShutdownConfig, SteamConfig};
+    use crate::games::{Game, ScriptedRunner, TmuxBackend};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn button_ids_round_trip() {
+        for verb in KICKING_VERBS {
+            let id = button_id(verb, "7days");
+            assert_eq!(parse_button(&id), Some((verb, "7days")));
+        }
+    }
+
+    #[test]
+    fn other_buttons_are_ignored() {
+        assert_eq!(parse_button("confirm:start:7days"), None);
+        assert_eq!(parse_button("server:stop:7days"), None);
+        assert_eq!(parse_button("confirm:stop:"), None);
+    }
+
+    #[tokio::test]
+    async fn an_update_that_would_be_refused_is_not_confirmed() {
+        let config: ServerConfig = toml::from_str(
+            r#"
+            name        = "enshrouded"
+            description = "Enshrouded"
+            port        = 15636
+            user        = "gs_enshrouded"
+            help        = "help"
+            backend     = { kind = "tmux", session = "enshrouded_server", command = "./enshrouded_server.exe" }
+            "#,
+        )
+        .unwrap();
+        // the session is up, so steamcmd must not run
+        let host = Arc::new(ScriptedRunner::new().exits(0));
+        let backend = TmuxBackend::new(
+            "gs_enshrouded",
+            "enshrouded_server",
+            "./enshrouded_server.exe",
+            Some(SteamConfig {
+                app_id: 2278520,
+                platform: None,
+            }),
+            ShutdownConfig::default(),
+            host.clone(),
+        );
+        let game = Game::new(config, Box::new(backend), None, None);
+
+        assert!(confirmation(Verb::Update, &game).await.is_none());
+        // refused before anyone was counted, let alone warned
+        assert_eq!(
+            host.calls(),
+            ["sudo -u gs_enshrouded tmux has-session -t enshrouded_server"]
+        );
+    }
+
+    #[test]
+    fn prompt_names_the_players() {
+        let info = ServerInfo {
+            players: 2,
+            max_players: 8,
+            player_names: vec!["alice".to_string(), "bob".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            prompt(Verb::Stop, "7days", &info),
+            "2 players are online on 7days (alice, bob). \
+             They will get 60 seconds to log off. Stop anyway?"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::games::{self, GameServerError};
+
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+/// Discord refuses messages longer than this
+const MESSAGE_LIMIT: usize = 2000;
+
+pub fn register() -> CreateCommand {
+    let mut options =
+        CreateCommandOption::new(CommandOptionType::String, "game", "Name of the game server")
+            .required(true);
+
+    // only servers with an RCON console make sense here
+    for game in games::all()
+        .iter()
+        .filter(|game| !game.console_commands().is_empty())
+    {
+        options = options.add_string_choice(game.name(), game.name())
+    }
+
+    CreateCommand::new("console")
+        .description("Run an in-game admin command")
+        .add_option(options)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "command", "Console command")
+                .required(true),
+        )
+}
+
+pub async fn run(options: &[ResolvedOption<'_>], user: &str) -> String {
+    let option = |name: &str| {
+        options.iter().find_map(|option| match option.value {
+            ResolvedValue::String(value) if option.name == name => Some(value),
+            _ => None,
+        })
+    };
+
+    let (Some(game), Some(command)) = (option("game"), option("command")) else {
+        return "Please provide a game and a command".to_string();
+    };
+
+    let Some(server) = games::get_game_server(game) else {
+        return "Please provide a valid game name".to_string();
+    };
+
+    println!("{user} ran `{command}` on the {game} console");
+
+    match server.console(command).await {
+        Ok(output) => code_block(&output),
+        Err(GameServerError::NotAllowed(name)) => format!(
+            "`{name}` is not allowed on the {game} console, try one of: {}",
+            server.console_commands().join(", ")
+        ),
+        Err(error) => {
+            eprintln!("{game} console `{command}` failed: {}", error.details());
+            format!("The {game} console failed ({error}), ask Tony to check it")
+        }
+    }
+}
+
+/// Wrap console output in a code block that fits in a discord message
+fn code_block(output: &str) -> String {
+    const FENCES: &str = "```\n\n```";
+    const ELLIPSIS: &str = "\n...";
+
+    // a stray fence in the output would end the block early
+    let output = output.trim().replace("```", "'''");
+    if output.is_empty() {
+        return "Command ran with no output".to_string();
+    }
+
+    let limit = MESSAGE_LIMIT - FENCES.len();
+    if output.len() <= limit {
+        return format!("```\n{output}\n```");
+    }
+
+    let mut end = limit - ELLIPSIS.len();
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("```\n{}{ELLIPSIS}\n```", &output[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_output_is_truncated_to_fit() {
+        let message = code_block(&"é".repeat(3000));
+
+        assert!(message.len() <= MESSAGE_LIMIT);
+        assert!(message.starts_with("```\n"));
+        assert!(message.ends_with("\n...\n```"));
+    }
+
+    #[test]
+    fn fences_in_output_are_defused() {
+        assert_eq!(code_block("a```b"), "```\na'''b\n```");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::games::{self, GAME_SERVERS};
+use crate::games;
 
 use serenity::builder::{CreateCommand, CreateCommandOption};
 use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
@@ -8,7 +8,7 @@ pub fn register() -> CreateCommand {
         CreateCommandOption::new(CommandOptionType::String, "game", "Name of the game server")
             .required(false);
 
-    for game in GAME_SERVERS.iter() {
+    for game in games::all().iter() {
         options = options.add_string_choice(game.name(), game.name())
     }
 
@@ -17,7 +17,7 @@ pub fn register() -> CreateCommand {
         .add_option(options)
 }
 
-pub fn run(options: &[ResolvedOption]) -> String {
+pub async fn run(options: &[ResolvedOption<'_>]) -> String {
     if let Some(ResolvedOption {
         value: ResolvedValue::String(game),
         ..
@@ -25,7 +25,7 @@ pub fn run(options: &[ResolvedOption]) -> String {
     {
         // if the game name exists and is valid, print the specifics
         if let Some(server_config) = games::get_game_server(game) {
-            return server_config.help_message();
+            return server_config.help_message().await;
         }
     };
 
@@ -34,7 +34,7 @@ pub fn run(options: &[ResolvedOption]) -> String {
 }
 
 fn general_help() -> String {
-    let server_list: String = GAME_SERVERS
+    let server_list: String = games::all()
         .iter()
         .map(|server| format!("- `{}` - {}\n", server.name(), server.description()))
         .collect();
@@ -45,17 +45,27 @@ fn general_help() -> String {
         ## Slash Commands\n\
         General commands:\n\
         ```\n\
-        /help : Show this general help info\n\
-        /ip   : Latest public IP\n\
-        /list : List of available servers and their status\n\n\
+        /help          : Show this general help info\n\
+        /ip            : Latest public IP\n\
+        /list          : Servers and their status, with start/stop buttons\n\
+        /checkupdates  : Servers with an update waiting\n\
+        /schedule list : Upcoming scheduled operations\n\n\
         ```\n\
         Game-specific commands:\n\
         ```\n\
-        /help    <game> : How to set up your game\n\
-        /start   <game> : Start the server\n\
-        /stop    <game> : Stop the server\n\
-        /restart <game> : Restart the server\n\
-        /update  <game> : Update the server if possible\n\
+        /help    <game>             : How to set up your game\n\
+        /start   <game>             : Start the server\n\
+        /stop    <game>             : Stop the server\n\
+        /restart <game>             : Restart the server\n\
+        /update  <game>             : Update the server if possible\n\
+        /history <game>             : Recent operations and crashes\n\
+        /backup  list|create <game> : List or make backups of the saves\n\
+        ```\n\
+        Admin commands (admin role unless `servers.toml` says otherwise):\n\
+        ```\n\
+        /console <game> <command>   : Run an allowed in-game console command\n\
+        /schedule add|remove        : Change scheduled operations\n\
+        /backup restore <game> <id> : Restore a backup over the current save\n\
         ```\n\
         All `/` commands have autocomplete suggestions for game selection\n\
         ## Available game servers\n\
@@ -0,0 +1,105 @@
+use crate::games::{self, format_duration};
+use crate::store::{self, History};
+
+use chrono::{DateTime, Local};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use std::time::{Duration, SystemTime};
+
+/// Operations shown, newest first
+const OPERATION_LIMIT: usize = 10;
+
+/// How far back the peak player count looks
+const PEAK_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+pub fn register() -> CreateCommand {
+    let mut options =
+        CreateCommandOption::new(CommandOptionType::String, "game", "Name of the game server")
+            .required(true);
+
+    for game in games::all().iter() {
+        options = options.add_string_choice(game.name(), game.name())
+    }
+
+    CreateCommand::new("history")
+        .description("Recent operations, crashes and player counts of a game server")
+        .add_option(options)
+}
+
+pub fn run(options: &[ResolvedOption<'_>]) -> String {
+    let Some(ResolvedOption {
+        value: ResolvedValue::String(game),
+        ..
+    }) = options.first()
+    else {
+        return "Please provide a valid game name".to_string();
+    };
+
+    let Some(server) = games::get_game_server(game) else {
+        return "Please provide a valid game name".to_string();
+    };
+
+    let Some(store) = store::get() else {
+        return "There is no history without a database, ask Tony".to_string();
+    };
+
+    let since = SystemTime::now() - PEAK_WINDOW;
+    match store.history(server.name(), OPERATION_LIMIT, since) {
+        Ok(history) => format_history(server.name(), &history),
+        Err(e) => {
+            eprintln!("Failed to read the history of {}: {e}", server.name());
+            format!("Could not read the history of {}, ask Tony", server.name())
+        }
+    }
+}
+
+fn format_history(name: &str, history: &History) -> String {
+    let mut message = format!("## History of {name}\n");
+
+    let last_crash = history
+        .last_crash
+        .map(timestamp)
+        .unwrap_or_else(|| "never".to_string());
+    message.push_str(&format!("- Last crash: {last_crash}\n"));
+
+    let peak = history
+        .peak_players
+        .map(|players| players.to_string())
+        .unwrap_or_else(|| "nobody seen".to_string());
+    message.push_str(&format!("- Most players this week: {peak}\n"));
+
+    if history.operations.is_empty() {
+        message.push_str("\nNothing has been run on it yet\n");
+        return message;
+    }
+
+    message.push_str("### Recent operations\n");
+    for operation in &history.operations {
+        let mark = if operation.success { "✅" } else { "❌" };
+        message.push_str(&format!(
+            "- {mark} {} {} by {} ({}, took {})\n",
+            timestamp(operation.started_at),
+            operation.verb,
+            operation.actor,
+            operation.outcome,
+            took(operation.duration)
+        ));
+    }
+
+    message
+}
+
+fn timestamp(time: SystemTime) -> String {
+    let time: DateTime<Local> = time.into();
+    time.format("%a %d %b %H:%M").to_string()
+}
+
+/// Most operations take seconds, so those are worth showing
+fn took(duration: Duration) -> String {
+    if duration < Duration::from_secs(60) {
+        format!("{}s", duration.as_secs())
+    } else {
+        format_duration(duration)
+    }
+}
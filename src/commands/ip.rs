@@ -1,4 +1,4 @@
-use crate::games;
+use crate::ip;
 
 use serenity::builder::CreateCommand;
 
@@ -7,5 +7,5 @@ pub fn register() -> CreateCommand {
 }
 
 pub fn run() -> String {
-    format!("The current server ip is {}", games::public_ip())
+    format!("The current server ip is {}", ip::public_ip())
 }
@@ -1,26 +1,141 @@
-use crate::games::GAME_SERVERS;
+use crate::config::Verb;
+use crate::games::{self, GameServer, ServerStatus, StatusReport};
 
-use serenity::builder::CreateCommand;
+use serenity::builder::{CreateActionRow, CreateButton, CreateCommand, CreateEmbed};
+use serenity::model::Colour;
+use serenity::model::application::ButtonStyle;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Discord allows 10 embeds and 5 rows of buttons per message
+pub const CARDS_PER_MESSAGE: usize = 5;
+
+/// Start of every button id, followed by the verb and server name
+const BUTTON_PREFIX: &str = "server";
+
+/// Verbs that get a button under each server
+const BUTTON_VERBS: [Verb; 3] = [Verb::Start, Verb::Stop, Verb::Restart];
+
+/// One server's embed and buttons, plus a line of text for the logs
+pub struct Card {
+    pub embed: CreateEmbed,
+    pub buttons: CreateActionRow,
+    pub summary: String,
+}
 
 pub fn register() -> CreateCommand {
     CreateCommand::new("list").description("List current status of all servers")
 }
 
-pub fn run() -> String {
-    let status_list: String = GAME_SERVERS
+pub async fn run() -> Vec<Card> {
+    let mut cards = Vec::new();
+    for server in games::all() {
+        cards.push(card(&**server, &server.status().await));
+    }
+    cards
+}
+
+pub fn card(server: &dyn GameServer, report: &StatusReport) -> Card {
+    let mut embed = CreateEmbed::new()
+        .title(server.name())
+        .description(server.description())
+        .colour(colour(&report.status))
+        .field("Status", report.status.to_string(), true)
+        .field("Port", server.port().to_string(), true);
+
+    // discord keeps relative timestamps ticking, so old messages stay right
+    let started = report
+        .uptime
+        .filter(|_| report.status.is_up())
+        .and_then(|uptime| SystemTime::now().checked_sub(uptime))
+        .and_then(|started| started.duration_since(UNIX_EPOCH).ok());
+    if let Some(started) = started {
+        embed = embed.field("Up since", format!("<t:{}:R>", started.as_secs()), true);
+    }
+
+    // version, map, MOTD and who is online, whatever the game shares
+    if let Some(info) = &report.info {
+        embed = embed.field("Game", info.details(), false);
+    }
+
+    let buttons = BUTTON_VERBS
         .iter()
-        .map(|server| {
-            format!(
-                "- `{}` - {}\n  - {}\n",
-                server.name(),
-                server.status(),
-                server.description()
-            )
+        .map(|verb| {
+            let (label, style) = match verb {
+                Verb::Start => ("Start", ButtonStyle::Success),
+                Verb::Stop => ("Stop", ButtonStyle::Danger),
+                _ => ("Restart", ButtonStyle::Primary),
+            };
+            CreateButton::new(button_id(*verb, server.name()))
+                .label(label)
+                .style(style)
         })
         .collect();
 
-    format!(
-        "## List of brumders servers\n\
-        {status_list}\n"
-    )
+    Card {
+        embed,
+        buttons: CreateActionRow::Buttons(buttons),
+        summary: format!("{}: {}", server.name(), report.summary()),
+    }
+}
+
+/// Green when it is playable, red when something is wrong, yellow when busy
+fn colour(status: &ServerStatus) -> Colour {
+    match status {
+        ServerStatus::Running => Colour::DARK_GREEN,
+        ServerStatus::Stopped => Colour::LIGHT_GREY,
+        ServerStatus::Crashed | ServerStatus::Unknown(_) => Colour::RED,
+        _ => Colour::GOLD,
+    }
+}
+
+/// e.g. `server:restart:skyvaults`
+fn button_id(verb: Verb, game: &str) -> String {
+    format!("{BUTTON_PREFIX}:{}:{game}", verb.name())
+}
+
+/// The verb and server name of a button from [run], if it is one
+pub fn parse_button(id: &str) -> Option<(Verb, &str)> {
+    let mut parts = id.splitn(3, ':');
+    if parts.next()? != BUTTON_PREFIX {
+        return None;
+    }
+
+    let verb = Verb::from_name(parts.next()?).filter(|verb| BUTTON_VERBS.contains(verb))?;
+    let game = parts.next().filter(|game| !game.is_empty())?;
+    Some((verb, game))
+}
+
+/// Run whatever a button asks for, on behalf of `user`
+pub async fn press(verb: Verb, game: &str, user: &str) -> String {
+    let Some(server) = games::get_game_server(game) else {
+        return format!("There is no {game} server any more");
+    };
+
+    match verb {
+        Verb::Start => super::run_operation(server, "start", user, server.start()).await,
+        Verb::Stop => super::run_operation(server, "stop", user, server.stop()).await,
+        _ => super::run_operation(server, "restart", user, server.restart()).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_ids_round_trip() {
+        for verb in BUTTON_VERBS {
+            let id = button_id(verb, "skyvaults");
+            assert_eq!(parse_button(&id), Some((verb, "skyvaults")));
+        }
+    }
+
+    #[test]
+    fn other_buttons_are_ignored() {
+        assert_eq!(parse_button("server:update:skyvaults"), None);
+        assert_eq!(parse_button("confirm:stop:skyvaults"), None);
+        assert_eq!(parse_button("server:start:"), None);
+        assert_eq!(parse_button("server"), None);
+    }
 }
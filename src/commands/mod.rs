@@ -1,7 +1,150 @@
+pub mod backup;
+pub mod checkupdates;
+pub mod confirm;
+pub mod console;
 pub mod help;
+pub mod history;
 pub mod ip;
 pub mod list;
 pub mod restart;
+pub mod schedule;
 pub mod start;
 pub mod stop;
 pub mod update;
+
+use crate::config::Verb;
+use crate::games::{GameServer, GameServerError, OperationOutcome, OperationResult};
+use crate::{locks, store};
+
+use serenity::model::application::{ResolvedOption, ResolvedValue};
+
+use std::time::{Instant, SystemTime};
+
+/// The `game` option, wherever it is nested
+pub fn find_game<'a>(options: &[ResolvedOption<'a>]) -> Option<&'a str> {
+    options.iter().find_map(|option| match &option.value {
+        ResolvedValue::String(game) if option.name == "game" => Some(*game),
+        ResolvedValue::SubCommand(inner) | ResolvedValue::SubCommandGroup(inner) => {
+            find_game(inner)
+        }
+        _ => None,
+    })
+}
+
+/// What a command asks permission for, e.g. `/backup restore` is [Verb::Restore]
+pub fn verb(name: &str, options: &[ResolvedOption<'_>]) -> Option<Verb> {
+    let subcommand = options.first().and_then(|option| match option.value {
+        ResolvedValue::SubCommand(_) => Some(option.name),
+        _ => None,
+    });
+
+    match (name, subcommand) {
+        ("backup", Some("restore")) => Some(Verb::Restore),
+        // seeing what is scheduled is as harmless as `/list`
+        ("schedule", Some("list")) => Some(Verb::List),
+        // so is asking which servers are behind
+        ("checkupdates", _) => Some(Verb::List),
+        _ => Verb::from_name(name),
+    }
+}
+
+/// Run an operation on behalf of `actor`, record it in the history and
+/// report how it went
+///
+/// The actor is a discord user name, or whichever task ran it. Nothing runs
+/// if another operation already holds the server.
+pub async fn run_operation<F>(
+    server: &dyn GameServer,
+    verb: &str,
+    actor: &str,
+    operation: F,
+) -> String
+where
+    F: Future<Output = OperationResult>,
+{
+    let _lock = match locks::acquire(server.name(), verb, actor) {
+        Ok(lock) => lock,
+        Err(busy) => return busy.to_string(),
+    };
+
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let result = operation.await;
+
+    store::record("operation", |store| {
+        store.record_operation(
+            server.name(),
+            verb,
+            actor,
+            &result,
+            started_at,
+            timer.elapsed(),
+        )
+    });
+
+    report(server, verb, result)
+}
+
+/// Turn the result of a game server operation into a message for discord
+///
+/// Failures are logged in full, but only summarised for discord
+pub fn report(server: &dyn GameServer, verb: &str, result: OperationResult) -> String {
+    let name = server.name();
+    let address = || format!("{}:{}", crate::ip::public_ip(), server.port());
+
+    match result {
+        Ok(OperationOutcome::Started) => {
+            format!("The {name} server started successfully ({})", address())
+        }
+        Ok(OperationOutcome::AlreadyRunning) => {
+            format!("The {name} server is already running ({})", address())
+        }
+        Ok(OperationOutcome::Stopped) => format!("The {name} server stopped successfully"),
+        Ok(OperationOutcome::ForceStopped) => {
+            format!("The {name} server did not shut down in time and was killed")
+        }
+        Ok(OperationOutcome::AlreadyStopped) => format!("The {name} server is already stopped"),
+        Ok(OperationOutcome::ForceRestarted) => format!(
+            "The {name} server did not shut down in time and was killed, then started again ({})",
+            address()
+        ),
+        Ok(OperationOutcome::Restarted) => {
+            format!("The {name} server restarted successfully ({})", address())
+        }
+        Ok(OperationOutcome::Updated {
+            from: Some(from),
+            to: Some(to),
+        }) => format!("The {name} server updated {from} → {to}"),
+        Ok(OperationOutcome::Updated { to: Some(to), .. }) => {
+            format!("The {name} server updated to build {to}")
+        }
+        Ok(OperationOutcome::Updated { .. }) => format!("The {name} server updated successfully"),
+        Ok(OperationOutcome::UpToDate(Some(build))) => {
+            format!("The {name} server is already up to date (build {build})")
+        }
+        Ok(OperationOutcome::UpToDate(None)) => format!("The {name} server is already up to date"),
+        Ok(OperationOutcome::BackedUp) => format!("The {name} server was backed up successfully"),
+        Ok(OperationOutcome::Restored) => format!("The {name} server was restored successfully"),
+        Err(error @ GameServerError::PlayersOnline(_)) => {
+            format!("The {name} server can not {verb} while {error}")
+        }
+        Err(GameServerError::ServerRunning) => {
+            format!("The {name} server is running, stop it first")
+        }
+        Err(GameServerError::UnknownBackup(id)) => {
+            format!("The {name} server has no backup called `{id}`, see `/backup list`")
+        }
+        Err(GameServerError::NotSupported(reason)) => {
+            format!("The {name} server {reason}, ask Tony")
+        }
+        Err(error) => {
+            eprintln!("{name} {verb} failed: {}", error.details());
+            match error {
+                GameServerError::Timeout { .. } => {
+                    format!("The {name} server {verb} {error}, ask Tony to check it")
+                }
+                _ => format!("The {name} server failed to {verb} ({error}), ask Tony to fix it"),
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::games::{self, GAME_SERVERS};
+use crate::games;
 
 use serenity::builder::{CreateCommand, CreateCommandOption};
 use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
@@ -8,7 +8,7 @@ pub fn register() -> CreateCommand {
         CreateCommandOption::new(CommandOptionType::String, "game", "Name of the game server")
             .required(true);
 
-    for game in GAME_SERVERS.iter() {
+    for game in games::all().iter() {
         options = options.add_string_choice(game.name(), game.name())
     }
 
@@ -17,7 +17,7 @@ pub fn register() -> CreateCommand {
         .add_option(options)
 }
 
-pub fn run(options: &[ResolvedOption]) -> String {
+pub async fn run(options: &[ResolvedOption<'_>], user: &str) -> String {
     if let Some(ResolvedOption {
         value: ResolvedValue::String(game),
         ..
@@ -25,7 +25,8 @@ pub fn run(options: &[ResolvedOption]) -> String {
     {
         // check if this exists and is valid
         if let Some(server_config) = games::get_game_server(game) {
-            return server_config.restart();
+            return super::run_operation(server_config, "restart", user, server_config.restart())
+                .await;
         }
     };
 
@@ -0,0 +1,159 @@
+use crate::config::ScheduledAction;
+use crate::games;
+use crate::schedule::{self, Cron, RemoveError};
+
+use chrono::Local;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+pub fn register() -> CreateCommand {
+    let mut game =
+        CreateCommandOption::new(CommandOptionType::String, "game", "Name of the game server")
+            .required(true);
+
+    for server in games::all().iter() {
+        game = game.add_string_choice(server.name(), server.name())
+    }
+
+    let mut action =
+        CreateCommandOption::new(CommandOptionType::String, "action", "What to do").required(true);
+
+    for action_choice in ScheduledAction::ALL {
+        action = action.add_string_choice(action_choice.verb(), action_choice.verb())
+    }
+
+    let cron = CreateCommandOption::new(
+        CommandOptionType::String,
+        "cron",
+        "minute hour day month weekday, e.g. \"0 5 * * *\" for 05:00 daily",
+    )
+    .required(true);
+
+    let id = CreateCommandOption::new(CommandOptionType::Integer, "id", "Schedule id from list")
+        .required(true);
+
+    CreateCommand::new("schedule")
+        .description("Scheduled restarts and maintenance")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List every schedule",
+        ))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add a schedule")
+                .add_sub_option(game)
+                .add_sub_option(action)
+                .add_sub_option(cron),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a schedule")
+                .add_sub_option(id),
+        )
+}
+
+pub fn run(options: &[ResolvedOption<'_>]) -> String {
+    let Some(ResolvedOption {
+        name,
+        value: ResolvedValue::SubCommand(options),
+        ..
+    }) = options.first()
+    else {
+        return "Please choose list, add or remove".to_string();
+    };
+
+    match *name {
+        "list" => list(),
+        "add" => add(options),
+        "remove" => remove(options),
+        _ => "Please choose list, add or remove".to_string(),
+    }
+}
+
+fn list() -> String {
+    let schedules = schedule::all();
+    if schedules.is_empty() {
+        return "Nothing is scheduled".to_string();
+    }
+
+    let now = Local::now().naive_local();
+    let lines: String = schedules
+        .iter()
+        .map(|s| {
+            let next = s
+                .cron
+                .next_after(now)
+                .map(|next| next.format("%a %d %b %H:%M").to_string())
+                .unwrap_or_else(|| "never".to_string());
+            let origin = if s.from_config { " (servers.toml)" } else { "" };
+            format!(
+                "- `#{}` {} {} at `{}` (next: {next}){origin}\n",
+                s.id,
+                s.action.verb(),
+                s.game,
+                s.cron
+            )
+        })
+        .collect();
+
+    format!("## Scheduled operations\n{lines}")
+}
+
+fn add(options: &[ResolvedOption<'_>]) -> String {
+    let option = |name: &str| {
+        options.iter().find_map(|option| match option.value {
+            ResolvedValue::String(value) if option.name == name => Some(value),
+            _ => None,
+        })
+    };
+
+    let (Some(game), Some(action), Some(cron)) = (option("game"), option("action"), option("cron"))
+    else {
+        return "Please provide a game, action and cron expression".to_string();
+    };
+
+    if games::get_game_server(game).is_none() {
+        return "Please provide a valid game name".to_string();
+    }
+
+    let Some(action) = ScheduledAction::from_verb(action) else {
+        return "Please provide a valid action".to_string();
+    };
+
+    let cron = match Cron::parse(cron) {
+        Ok(cron) => cron,
+        Err(reason) => return format!("Invalid schedule: {reason}"),
+    };
+
+    let schedule = schedule::add(game, action, cron);
+    format!(
+        "Added `#{}`: {} {} at `{}`",
+        schedule.id,
+        schedule.action.verb(),
+        schedule.game,
+        schedule.cron
+    )
+}
+
+fn remove(options: &[ResolvedOption<'_>]) -> String {
+    let id = options.iter().find_map(|option| match option.value {
+        ResolvedValue::Integer(id) if option.name == "id" => u32::try_from(id).ok(),
+        _ => None,
+    });
+
+    match id.map(schedule::remove) {
+        Some(Ok(schedule)) => format!(
+            "Removed `#{}`: {} {} at `{}`",
+            schedule.id,
+            schedule.action.verb(),
+            schedule.game,
+            schedule.cron
+        ),
+        Some(Err(RemoveError::FromConfig(schedule))) => format!(
+            "`#{}` is set in servers.toml, ask Tony to change it there",
+            schedule.id
+        ),
+        Some(Err(RemoveError::NotFound)) | None => {
+            "There is no schedule with that id, see `/schedule list`".to_string()
+        }
+    }
+}
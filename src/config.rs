@@ -0,0 +1,943 @@
+use crate::schedule::Cron;
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs, io};
+
+/// Environment variable to override the location of the server config
+const ENV_CONFIG_PATH: &str = "MULTIPLAYER_BOT_CONFIG";
+
+/// Default location of the server config, relative to the working directory
+const DEFAULT_CONFIG_PATH: &str = "servers.toml";
+
+/// Discord only allows 25 choices for a slash command option
+const MAX_SERVERS: usize = 25;
+
+/// Roles that always exist, so can not be declared in `[roles]`
+pub const BUILTIN_ROLES: [&str; 3] = ["everyone", "trusted", "admin"];
+
+/// Everything declared in `servers.toml`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Every game server the bot should know about
+    #[serde(default)]
+    pub servers: Vec<ServerConfig>,
+    /// Automatic restarts for servers that crash
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    /// How the public IP is looked up
+    #[serde(default)]
+    pub ip: IpConfig,
+    /// When to look for pending updates in the background
+    #[serde(default)]
+    pub update_check: UpdateCheckConfig,
+    /// Discord role ids by name, for use in `permissions`
+    #[serde(default)]
+    pub roles: BTreeMap<String, u64>,
+    /// Roles allowed to use each command on every server
+    #[serde(default)]
+    pub permissions: Permissions,
+}
+
+/// Role names allowed to use each command
+pub type Permissions = BTreeMap<Verb, Vec<String>>;
+
+/// Everything a member can be allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verb {
+    Help,
+    Ip,
+    List,
+    History,
+    Start,
+    Stop,
+    Restart,
+    Update,
+    /// Listing and creating backups
+    Backup,
+    /// Restoring a backup over the current save
+    Restore,
+    Console,
+    /// Adding and removing schedules
+    Schedule,
+}
+
+impl Verb {
+    pub const ALL: [Verb; 12] = [
+        Verb::Help,
+        Verb::Ip,
+        Verb::List,
+        Verb::History,
+        Verb::Start,
+        Verb::Stop,
+        Verb::Restart,
+        Verb::Update,
+        Verb::Backup,
+        Verb::Restore,
+        Verb::Console,
+        Verb::Schedule,
+    ];
+
+    /// Name used in config and messages, e.g. "restore"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verb::Help => "help",
+            Verb::Ip => "ip",
+            Verb::List => "list",
+            Verb::History => "history",
+            Verb::Start => "start",
+            Verb::Stop => "stop",
+            Verb::Restart => "restart",
+            Verb::Update => "update",
+            Verb::Backup => "backup",
+            Verb::Restore => "restore",
+            Verb::Console => "console",
+            Verb::Schedule => "schedule",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|verb| verb.name() == name)
+    }
+
+    /// Whether the verb is ever used on a particular server
+    pub fn per_server(&self) -> bool {
+        !matches!(self, Verb::Ip | Verb::List)
+    }
+}
+
+/// The `[watchdog]` table, minutes throughout
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// Set to false to only ever start servers by hand
+    pub enabled: bool,
+    /// Restarts allowed within `window` before giving up
+    pub max_restarts: u32,
+    /// How far back restarts are counted
+    pub window: u64,
+    /// Wait after the first restart, doubled after every one since
+    pub backoff: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            enabled: true,
+            max_restarts: 3,
+            window: 60,
+            backoff: 1,
+        }
+    }
+}
+
+/// The `[ip]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpConfig {
+    /// Services answering with the caller's IP as plain text, tried in order
+    pub providers: Vec<String>,
+    /// Minutes before the IP is looked up again
+    pub ttl: u64,
+}
+
+impl Default for IpConfig {
+    fn default() -> Self {
+        IpConfig {
+            providers: vec![
+                "https://ident.me".to_string(),
+                "https://api.ipify.org".to_string(),
+                "https://icanhazip.com".to_string(),
+            ],
+            ttl: 10,
+        }
+    }
+}
+
+/// The `[update_check]` table
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpdateCheckConfig {
+    /// When to check every server, e.g. `0 9 * * *`, or never if unset
+    pub cron: Option<Cron>,
+}
+
+/// A single `[[servers]]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Name used for slash commands, e.g. `/start <name>`
+    pub name: String,
+    /// Short description shown in `/help` and `/list`
+    pub description: String,
+    /// Port players connect to
+    pub port: u16,
+    /// User that runs the game server
+    pub user: String,
+    /// Markdown help text, `{ip}` and `{port}` are substituted
+    pub help: String,
+    /// Set to false to keep the config around without exposing the server
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Set to true for servers that can not be updated by the bot
+    #[serde(default)]
+    pub manual_update: bool,
+    /// How long each operation may take before it is killed
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    /// How to ask the game itself for player counts
+    pub query: Option<QueryConfig>,
+    /// In-game admin console for `/console`
+    pub rcon: Option<RconConfig>,
+    /// Minutes without players before the server is stopped, needs a `query`
+    pub idle_timeout: Option<u64>,
+    /// Operations to run on a timetable, e.g. a nightly restart
+    #[serde(default)]
+    pub schedules: Vec<ScheduleConfig>,
+    /// Where to archive saves, for servers LGSM does not back up
+    pub backup: Option<BackupConfig>,
+    /// How to check for updates, for backends that can not do it themselves
+    pub versions: Option<VersionsConfig>,
+    /// Extra roles allowed to use commands on this server only
+    #[serde(default)]
+    pub permissions: Permissions,
+    /// How the game server process is managed on the host
+    pub backend: BackendConfig,
+}
+
+/// The `backend` table of a server, selected by `kind`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum BackendConfig {
+    /// Linux Game Server Manager script, e.g. `/home/<user>/<runner> start`
+    Lgsm {
+        /// Name of the LGSM script in the user's home directory
+        runner: String,
+    },
+    /// Detached tmux session running the server executable
+    Tmux {
+        /// tmux session name
+        session: String,
+        /// Server executable (and arguments) to run inside the session
+        command: String,
+        /// Steam app to update through steamcmd
+        steam: Option<SteamConfig>,
+        /// How to ask the server to exit before the session is killed
+        #[serde(default)]
+        shutdown: ShutdownConfig,
+    },
+    /// Systemd unit controlled with `systemctl`
+    Systemd {
+        /// Name of the unit, e.g. `hytale.service`
+        unit: String,
+    },
+}
+
+/// The `shutdown` table of a tmux backend
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// Typed into the server console followed by enter, e.g. `quit`, or
+    /// Ctrl+C (SIGINT) when unset
+    pub input: Option<String>,
+    /// Seconds to wait for the server to exit before killing it
+    pub timeout: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        ShutdownConfig {
+            input: None,
+            timeout: 60,
+        }
+    }
+}
+
+/// Steam app details for servers updated through steamcmd
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SteamConfig {
+    /// Steam app id of the dedicated server
+    pub app_id: u32,
+    /// Force a platform for windows-only servers run through wine
+    pub platform: Option<String>,
+}
+
+/// The `query` table of a server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryConfig {
+    /// Query protocol the game speaks
+    pub protocol: QueryProtocol,
+    /// Query port, if different to the game port
+    pub port: Option<u16>,
+    /// Host to query, the bot normally runs on the game server host
+    #[serde(default = "default_query_host")]
+    pub host: String,
+    /// Minutes a running server may ignore queries while it loads, after
+    /// which it is reported as unresponsive rather than starting
+    #[serde(default = "default_startup_grace")]
+    pub startup_grace: u64,
+    /// Environment variable holding the API token, for protocols that need one
+    pub token_env: Option<String>,
+}
+
+/// The `rcon` table of a server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RconConfig {
+    /// RCON port set in the game's own config
+    pub port: u16,
+    /// Host running the RCON listener, normally the bot's own host
+    #[serde(default = "default_query_host")]
+    pub host: String,
+    /// Environment variable holding the RCON password, so it stays out of
+    /// the config file
+    pub password_env: String,
+    /// Console commands admins may run, matched against the first word
+    pub allow: Vec<String>,
+    /// Command that messages every player, with `{message}` filled in
+    pub broadcast: Option<String>,
+}
+
+/// The `backup` table of a server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupConfig {
+    /// Save directory to archive
+    pub path: String,
+    /// Directory the archives are kept in
+    pub dir: String,
+    /// Number of archives to keep, oldest are deleted first
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+}
+
+/// The `versions` table of a server, shell commands run as the server user
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionsConfig {
+    /// Prints the installed version, e.g. `cat server/version.txt`
+    pub installed: String,
+    /// Prints the latest version, e.g. `./hytale-downloader -print-version`
+    pub available: String,
+}
+
+/// An entry in the `schedules` list of a server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleConfig {
+    pub action: ScheduledAction,
+    /// When to run it, e.g. `0 5 * * *` for 05:00 every day
+    pub cron: Cron,
+}
+
+/// Operations that can be scheduled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduledAction {
+    Start,
+    Stop,
+    Restart,
+    Update,
+}
+
+impl ScheduledAction {
+    pub const ALL: [ScheduledAction; 4] = [
+        ScheduledAction::Start,
+        ScheduledAction::Stop,
+        ScheduledAction::Restart,
+        ScheduledAction::Update,
+    ];
+
+    /// Name used in config and messages, e.g. "restart"
+    pub fn verb(&self) -> &'static str {
+        match self {
+            ScheduledAction::Start => "start",
+            ScheduledAction::Stop => "stop",
+            ScheduledAction::Restart => "restart",
+            ScheduledAction::Update => "update",
+        }
+    }
+
+    pub fn from_verb(verb: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.verb() == verb)
+    }
+}
+
+/// Supported query protocols
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryProtocol {
+    /// Valve A2S_INFO/A2S_PLAYER, spoken by most steam games
+    A2s,
+    /// Minecraft java edition Server List Ping
+    Minecraft,
+    /// Satisfactory dedicated server HTTPS API, needs `token_env`
+    Satisfactory,
+}
+
+/// Per-operation time limits in minutes, after which the command is killed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    pub start: u64,
+    pub stop: u64,
+    pub restart: u64,
+    pub update: u64,
+    /// Used for both creating and restoring backups
+    pub backup: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            start: 5,
+            stop: 5,
+            restart: 10,
+            update: 30,
+            backup: 30,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_query_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_startup_grace() -> u64 {
+    15
+}
+
+fn default_backup_keep() -> usize {
+    7
+}
+
+/// Anything that stops the config from being used
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read at all
+    Read { path: PathBuf, source: io::Error },
+    /// The file is not valid TOML or does not match the expected layout
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    /// A value is missing, of the wrong type or not usable, e.g. table
+    /// `[servers.7days]` and key `port`
+    Invalid {
+        table: String,
+        key: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "unable to read {}: {source}", path.display())
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "unable to parse {}: {source}", path.display())
+            }
+            ConfigError::Invalid { table, key, reason } => {
+                write!(f, "{table}.{key}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            ConfigError::Parse { source, .. } => Some(source),
+            ConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+/// Load the config from `MULTIPLAYER_BOT_CONFIG`, or `servers.toml` by default
+pub fn load() -> Result<Config, ConfigError> {
+    let path = env::var(ENV_CONFIG_PATH).unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+    load_from(path)
+}
+
+/// Load and validate the config at a specific path
+pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+    let path = path.as_ref();
+
+    let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    parse(path, &contents)
+}
+
+/// Parse and validate the contents of the config at `path`
+fn parse(path: &Path, contents: &str) -> Result<Config, ConfigError> {
+    let config: Config = toml::from_str(contents).map_err(|source| {
+        server_error(contents).unwrap_or_else(|| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    })?;
+
+    config.validate()?;
+    Ok(config)
+}
+
+/// The first `[[servers]]` table that does not deserialize on its own, by
+/// server name and key rather than the line number serde gives for the file
+fn server_error(contents: &str) -> Option<ConfigError> {
+    let file: toml::Table = toml::from_str(contents).ok()?;
+    let servers = file.get("servers")?.as_array()?;
+
+    servers.iter().enumerate().find_map(|(i, server)| {
+        let error = ServerConfig::deserialize(server.clone()).err()?;
+        let table = match server.get("name").and_then(toml::Value::as_str) {
+            Some(name) => server_table(name),
+            None => format!("servers[{i}]"),
+        };
+
+        // e.g. "invalid type: ..., expected u16\nin `port`", or just
+        // "missing field `port`" when there is no value to point at
+        let message = error.to_string();
+        let (reason, key) = match message.trim_end().split_once("\nin `") {
+            Some((reason, path)) => (reason, path.trim_end_matches('`')),
+            None => (message.trim_end(), message.split('`').nth(1)?),
+        };
+
+        Some(ConfigError::Invalid {
+            table,
+            key: key.to_string(),
+            reason: reason.to_string(),
+        })
+    })
+}
+
+/// e.g. `[servers.7days]`, prefixing every error about that server
+fn server_table(name: &str) -> String {
+    format!("[servers.{name}]")
+}
+
+impl Config {
+    /// Only the servers that should be exposed to discord
+    pub fn enabled_servers(&self) -> impl Iterator<Item = &ServerConfig> {
+        self.servers.iter().filter(|s| s.enabled)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut names = HashSet::new();
+
+        for (i, server) in self.servers.iter().enumerate() {
+            server.validate(i)?;
+
+            if !names.insert(server.name.as_str()) {
+                return Err(invalid(
+                    &server_table(&server.name),
+                    "name",
+                    format!("\"{}\" is used by more than one server", server.name),
+                ));
+            }
+        }
+
+        self.watchdog.validate("[watchdog]")?;
+        self.ip.validate("[ip]")?;
+
+        for name in self.roles.keys() {
+            if BUILTIN_ROLES.contains(&name.as_str()) {
+                return Err(invalid(
+                    "[roles]",
+                    "name",
+                    format!("\"{name}\" always exists and can not be redeclared"),
+                ));
+            }
+        }
+
+        self.validate_permissions("[permissions]", &self.permissions)?;
+        for server in &self.servers {
+            let table = format!("{}.permissions", server_table(&server.name));
+            self.validate_permissions(&table, &server.permissions)?;
+
+            if let Some(verb) = server.permissions.keys().find(|verb| !verb.per_server()) {
+                return Err(invalid(
+                    &table,
+                    "verb",
+                    format!("\"{}\" is not used on a single server", verb.name()),
+                ));
+            }
+        }
+
+        let enabled = self.enabled_servers().count();
+        if enabled > MAX_SERVERS {
+            return Err(invalid(
+                "[[servers]]",
+                "enabled",
+                format!("{enabled} servers are enabled but discord allows at most {MAX_SERVERS}"),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Every role named must be declared in `[roles]` or be built in
+    fn validate_permissions(
+        &self,
+        table: &str,
+        permissions: &Permissions,
+    ) -> Result<(), ConfigError> {
+        let unknown = permissions.values().flatten().find(|role| {
+            !BUILTIN_ROLES.contains(&role.as_str()) && !self.roles.contains_key(*role)
+        });
+
+        match unknown {
+            Some(role) => Err(invalid(
+                table,
+                "roles",
+                format!("\"{role}\" is not declared in [roles]"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Validate the server at `index` in the `[[servers]]` list
+    fn validate(&self, index: usize) -> Result<(), ConfigError> {
+        // discord choices are case sensitive and awkward to type with spaces
+        if self.name.is_empty()
+            || self.name.len() > 32
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            return Err(invalid(
+                &format!("servers[{index}]"),
+                "name",
+                format!(
+                    "\"{}\" must be 1-32 lowercase letters, digits, '-' or '_'",
+                    self.name
+                ),
+            ));
+        }
+
+        // everything past this point can name the server for context
+        let table = server_table(&self.name);
+
+        if self.description.trim().is_empty() {
+            return Err(invalid(&table, "description", "must not be empty"));
+        }
+
+        if self.port == 0 {
+            return Err(invalid(&table, "port", "must be a valid port number"));
+        }
+
+        if self.user.trim().is_empty() {
+            return Err(invalid(&table, "user", "must not be empty"));
+        }
+
+        if self.help.trim().is_empty() {
+            return Err(invalid(&table, "help", "must not be empty"));
+        }
+
+        self.timeouts.validate(&format!("{table}.timeouts"))?;
+
+        if let Some(query) = &self.query {
+            let table = format!("{table}.query");
+            non_empty(&table, "host", &query.host)?;
+            if query.port == Some(0) {
+                return Err(invalid(&table, "port", "must be a valid port number"));
+            }
+
+            match &query.token_env {
+                Some(token_env) => non_empty(&table, "token_env", token_env)?,
+                None if query.protocol == QueryProtocol::Satisfactory => {
+                    return Err(invalid(&table, "token_env", "is needed for satisfactory"));
+                }
+                None => {}
+            }
+        }
+
+        if let Some(minutes) = self.idle_timeout {
+            if minutes == 0 {
+                return Err(invalid(&table, "idle_timeout", "must be at least 1 minute"));
+            }
+            if self.query.is_none() {
+                return Err(invalid(
+                    &table,
+                    "idle_timeout",
+                    "needs a `query` table to count players",
+                ));
+            }
+        }
+
+        if let Some(backup) = &self.backup {
+            let table = format!("{table}.backup");
+            if matches!(self.backend, BackendConfig::Lgsm { .. }) {
+                return Err(invalid(
+                    &table,
+                    "path",
+                    "LGSM servers are backed up by LGSM, remove this table",
+                ));
+            }
+            backup.validate(&table)?;
+        }
+
+        if let Some(rcon) = &self.rcon {
+            rcon.validate(&format!("{table}.rcon"))?;
+        }
+
+        let table = format!("{table}.backend");
+        self.backend.validate(&table)?;
+
+        // the session is killed once the operation runs out of time anyway
+        if let BackendConfig::Tmux { shutdown, .. } = &self.backend {
+            let limit = self.timeouts.stop.min(self.timeouts.restart) * 60;
+            if shutdown.timeout >= limit {
+                return Err(invalid(
+                    &table,
+                    "shutdown.timeout",
+                    format!("must be shorter than timeouts.stop and timeouts.restart ({limit}s)"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BackupConfig {
+    fn validate(&self, table: &str) -> Result<(), ConfigError> {
+        for (key, path) in [("path", &self.path), ("dir", &self.dir)] {
+            if !path.starts_with('/') || path.trim_end_matches('/').is_empty() {
+                return Err(invalid(table, key, "must be an absolute path below /"));
+            }
+        }
+
+        if self.keep == 0 {
+            return Err(invalid(table, "keep", "must keep at least 1 backup"));
+        }
+
+        Ok(())
+    }
+}
+
+impl RconConfig {
+    fn validate(&self, table: &str) -> Result<(), ConfigError> {
+        if self.port == 0 {
+            return Err(invalid(table, "port", "must be a valid port number"));
+        }
+
+        non_empty(table, "host", &self.host)?;
+        non_empty(table, "password_env", &self.password_env)?;
+
+        if self.allow.is_empty() {
+            return Err(invalid(table, "allow", "must list at least one command"));
+        }
+
+        if let Some(command) = self
+            .allow
+            .iter()
+            .find(|c| c.trim().is_empty() || c.contains(' '))
+        {
+            return Err(invalid(
+                table,
+                "allow",
+                format!("\"{command}\" must be a single command name"),
+            ));
+        }
+
+        if let Some(broadcast) = &self.broadcast {
+            if !broadcast.contains("{message}") {
+                return Err(invalid(table, "broadcast", "must contain `{message}`"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BackendConfig {
+    fn validate(&self, table: &str) -> Result<(), ConfigError> {
+        match self {
+            BackendConfig::Lgsm { runner } => non_empty(table, "runner", runner),
+            BackendConfig::Tmux {
+                session, command, ..
+            } => {
+                non_empty(table, "session", session)?;
+                non_empty(table, "command", command)
+            }
+            BackendConfig::Systemd { unit } => non_empty(table, "unit", unit),
+        }
+    }
+}
+
+impl WatchdogConfig {
+    fn validate(&self, table: &str) -> Result<(), ConfigError> {
+        if self.max_restarts == 0 {
+            return Err(invalid(table, "max_restarts", "must be at least 1"));
+        }
+
+        for (key, minutes) in [("window", self.window), ("backoff", self.backoff)] {
+            if minutes == 0 {
+                return Err(invalid(table, key, "must be at least 1 minute"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IpConfig {
+    fn validate(&self, table: &str) -> Result<(), ConfigError> {
+        if self.providers.is_empty() {
+            return Err(invalid(table, "providers", "must list at least one"));
+        }
+
+        if let Some(provider) = self
+            .providers
+            .iter()
+            .find(|provider| !provider.starts_with("https://") && !provider.starts_with("http://"))
+        {
+            return Err(invalid(
+                table,
+                "providers",
+                format!("\"{provider}\" is not an http(s) url"),
+            ));
+        }
+
+        if self.ttl == 0 {
+            return Err(invalid(table, "ttl", "must be at least 1 minute"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Timeouts {
+    fn validate(&self, table: &str) -> Result<(), ConfigError> {
+        for (key, minutes) in [
+            ("start", self.start),
+            ("stop", self.stop),
+            ("restart", self.restart),
+            ("update", self.update),
+            ("backup", self.backup),
+        ] {
+            if minutes == 0 {
+                return Err(invalid(table, key, "must be at least 1 minute"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn non_empty(table: &str, key: &'static str, value: &str) -> Result<(), ConfigError> {
+    if value.trim().is_empty() {
+        Err(invalid(table, key, "must not be empty"))
+    } else {
+        Ok(())
+    }
+}
+
+fn invalid(table: &str, key: &str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid {
+        table: table.to_string(),
+        key: key.to_string(),
+        reason: reason.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &str = r#"
+        [[servers]]
+        name        = "7days"
+        description = "7 Days to Die"
+        port        = 26900
+        user        = "gs_7days"
+        help        = "help"
+        backend     = { kind = "lgsm", runner = "sdtdserver" }
+    "#;
+
+    /// The error from loading `toml`, which must not load
+    fn error(toml: &str) -> String {
+        parse(Path::new("servers.toml"), toml)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn the_example_config_loads() {
+        let config = load_from(DEFAULT_CONFIG_PATH).unwrap();
+        assert!(config.enabled_servers().count() > 0);
+    }
+
+    #[test]
+    fn a_missing_key_names_the_server() {
+        let toml = SERVER.replace("port        = 26900", "");
+        assert!(error(&toml).starts_with("[servers.7days].port: missing field"));
+    }
+
+    #[test]
+    fn a_wrong_type_names_the_server() {
+        let toml = SERVER.replace("26900", "\"26900\"");
+        assert_eq!(
+            error(&toml),
+            "[servers.7days].port: invalid type: string \"26900\", expected u16"
+        );
+    }
+
+    #[test]
+    fn an_unknown_backend_names_the_server() {
+        let toml = SERVER.replace("\"lgsm\"", "\"docker\"");
+        assert!(error(&toml).starts_with("[servers.7days].backend.kind: unknown variant `docker`"));
+    }
+
+    #[test]
+    fn a_duplicate_name_names_the_server() {
+        let toml = format!("{SERVER}{SERVER}");
+        assert_eq!(
+            error(&toml),
+            "[servers.7days].name: \"7days\" is used by more than one server"
+        );
+    }
+
+    #[test]
+    fn a_shutdown_must_fit_in_the_stop_timeout() {
+        let toml = SERVER.replace(
+            "{ kind = \"lgsm\", runner = \"sdtdserver\" }",
+            "{ kind = \"tmux\", session = \"7days\", command = \"./7DaysToDieServer.x86_64\", \
+             shutdown = { timeout = 300 } }",
+        );
+        assert_eq!(
+            error(&toml),
+            "[servers.7days].backend.shutdown.timeout: \
+             must be shorter than timeouts.stop and timeouts.restart (300s)"
+        );
+    }
+
+    #[test]
+    fn an_unusable_value_names_the_server() {
+        let toml = format!("{SERVER}        timeouts = {{ stop = 0 }}\n");
+        assert_eq!(
+            error(&toml),
+            "[servers.7days].timeouts.stop: must be at least 1 minute"
+        );
+    }
+}
@@ -1,15 +1,30 @@
 // internal
-use crate::{commands, tokens};
+use crate::audit::{self, Entry, Outcome};
+use crate::commands::confirm::{self, Confirmation};
+use crate::commands::list::{CARDS_PER_MESSAGE, Card};
+use crate::config::{Config, Verb};
+use crate::permissions::{Denied, Permissions};
+use crate::{commands, tasks, tokens};
 
 // discord API
-use serenity::all::CommandInteraction;
+use serenity::all::{CommandInteraction, ComponentInteraction};
 use serenity::async_trait;
-use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::builder::{
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    EditInteractionResponse,
+};
 use serenity::model::application::Interaction;
 use serenity::model::gateway::Ready;
 use serenity::prelude::*;
 
-pub struct Handler;
+use std::time::Instant;
+
+pub struct Handler {
+    /// Settings for everything beyond the game servers themselves
+    pub config: Config,
+    /// Who may use which commands
+    pub permissions: Permissions,
+}
 
 #[async_trait]
 impl EventHandler for Handler {
@@ -21,10 +36,15 @@ impl EventHandler for Handler {
             .set_commands(
                 &ctx.http,
                 vec![
+                    commands::backup::register(),
+                    commands::checkupdates::register(),
+                    commands::console::register(),
                     commands::help::register(),
+                    commands::history::register(),
                     commands::ip::register(),
                     commands::list::register(),
                     commands::restart::register(),
+                    commands::schedule::register(),
                     commands::start::register(),
                     commands::stop::register(),
                     commands::update::register(),
@@ -33,121 +53,470 @@ impl EventHandler for Handler {
             .await;
 
         println!("Registered guild slash commands: {commands:#?}");
+
+        tasks::start(&ctx, &self.config);
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::Command(command) = interaction {
-            // stop random members from messing with running servers
-            if !is_trusted_member(&command) {
-                let msg = CreateInteractionResponseMessage::new()
-                    .content("This bot is restricted to trusted memebers, ask someone to add you to the `Server Vet` role");
-                let builder = CreateInteractionResponse::Message(msg);
-                let _ = command.create_response(&ctx.http, builder).await;
-                return;
+        match interaction {
+            Interaction::Command(command) => self.run_command(&ctx, &command).await,
+            Interaction::Component(component) => self.press_button(&ctx, &component).await,
+            _ => {}
+        }
+    }
+}
+
+impl Handler {
+    async fn run_command(&self, ctx: &Context, command: &CommandInteraction) {
+        let started = Instant::now();
+        let entry = Entry::command(command);
+
+        // stop random members from messing with running servers
+        if let Err(denied) = self.check_permission(command) {
+            let reply = respond_immediately(ctx, command, denied.to_string()).await;
+            audit::record(
+                &ctx.http,
+                &entry,
+                Outcome::Denied,
+                &reply,
+                started.elapsed(),
+            )
+            .await;
+            return;
+        }
+
+        // slash commands
+        let mut outcome = Outcome::Completed;
+        let reply = match command.data.name.as_str() {
+            "backup" => {
+                respond_deferred(
+                    ctx,
+                    command,
+                    "Working on backups (may take several minutes)...",
+                    commands::backup::run(&command.data.options(), &command.user.name),
+                )
+                .await
+            }
+            "checkupdates" => {
+                respond_deferred(
+                    ctx,
+                    command,
+                    "Checking every server for updates (may take a few minutes)...",
+                    commands::checkupdates::run(),
+                )
+                .await
             }
+            "console" => {
+                respond_deferred(
+                    ctx,
+                    command,
+                    "Running console command...",
+                    commands::console::run(&command.data.options(), &command.user.name),
+                )
+                .await
+            }
+            "help" => {
+                respond_deferred(
+                    ctx,
+                    command,
+                    "Fetching help...",
+                    commands::help::run(&command.data.options()),
+                )
+                .await
+            }
+            "history" => {
+                let message = commands::history::run(&command.data.options());
+                respond_immediately(ctx, command, message).await
+            }
+            "ip" => {
+                let message = commands::ip::run();
+                respond_immediately(ctx, command, message).await
+            }
+            "list" => {
+                respond_cards(
+                    ctx,
+                    command,
+                    "Listing status of all game servers...",
+                    commands::list::run(),
+                )
+                .await
+            }
+            "schedule" => {
+                let message = commands::schedule::run(&command.data.options());
+                respond_immediately(ctx, command, message).await
+            }
+            "start" => {
+                respond_deferred(
+                    ctx,
+                    command,
+                    "Starting game server...",
+                    commands::start::run(&command.data.options(), &command.user.name),
+                )
+                .await
+            }
+            "stop" => {
+                let (reply, kicking) = respond_kicking(
+                    ctx,
+                    command,
+                    Verb::Stop,
+                    "Stopping game server (may take a few minutes)...",
+                    commands::stop::run(&command.data.options(), &command.user.name),
+                )
+                .await;
+                outcome = kicking;
+                reply
+            }
+            "restart" => {
+                let (reply, kicking) = respond_kicking(
+                    ctx,
+                    command,
+                    Verb::Restart,
+                    "Restarting game server...",
+                    commands::restart::run(&command.data.options(), &command.user.name),
+                )
+                .await;
+                outcome = kicking;
+                reply
+            }
+            "update" => {
+                let (reply, kicking) = respond_kicking(
+                    ctx,
+                    command,
+                    Verb::Update,
+                    "Updating game server (may take a several minutes)...",
+                    commands::update::run(&command.data.options(), &command.user.name),
+                )
+                .await;
+                outcome = kicking;
+                reply
+            }
+            unrecognised => {
+                respond_immediately(
+                    ctx,
+                    command,
+                    format!("\"{unrecognised}\" is not a recognised command"),
+                )
+                .await
+            }
+        };
 
-            // slash commands
-            match command.data.name.as_str() {
-                "help" => {
-                    let message = commands::help::run(&command.data.options());
-                    respond_immediately(&ctx, &command, message).await;
-                }
-                "ip" => {
-                    let message = commands::ip::run();
-                    respond_immediately(&ctx, &command, message).await;
-                }
-                "list" => {
-                    respond_deferred(
-                        &ctx,
-                        &command,
-                        "Listing status of all game servers...",
-                        commands::list::run,
-                    )
-                    .await;
-                }
-                "start" => {
-                    let cmd_data = command.data.clone();
-                    respond_deferred(&ctx, &command, "Starting game server...", move || {
-                        commands::start::run(&cmd_data.options())
-                    })
-                    .await;
-                }
-                "stop" => {
-                    let cmd_data = command.data.clone();
-                    respond_deferred(
-                        &ctx,
-                        &command,
-                        "Stopping game server (may take a few minutes)...",
-                        move || commands::stop::run(&cmd_data.options()),
-                    )
-                    .await;
-                }
-                "restart" => {
-                    let cmd_data = command.data.clone();
-                    respond_deferred(&ctx, &command, "Restarting game server...", move || {
-                        commands::restart::run(&cmd_data.options())
-                    })
-                    .await;
-                }
-                "update" => {
-                    let cmd_data = command.data.clone();
-                    respond_deferred(
-                        &ctx,
-                        &command,
-                        "Updating game server (may take a several minutes)...",
-                        move || commands::update::run(&cmd_data.options()),
-                    )
-                    .await;
-                }
-                unrecognised => {
-                    respond_immediately(
-                        &ctx,
-                        &command,
-                        format!("\"{unrecognised}\" is not a recognised command"),
-                    )
-                    .await;
+        audit::record(&ctx.http, &entry, outcome, &reply, started.elapsed()).await;
+    }
+
+    /// The start, stop and restart buttons under each server in `/list`, and
+    /// the "Stop anyway" style confirmations, checked against the same
+    /// permissions as the slash commands
+    async fn press_button(&self, ctx: &Context, component: &ComponentInteraction) {
+        let id = &component.data.custom_id;
+        let (verb, game, confirmed) = if let Some((verb, game)) = commands::list::parse_button(id) {
+            (verb, game, false)
+        } else if let Some((verb, game)) = confirm::parse_button(id) {
+            (verb, game, true)
+        } else {
+            return;
+        };
+
+        let started = Instant::now();
+        let entry = Entry::button(&component.user, verb, game);
+
+        let roles = component
+            .member
+            .as_ref()
+            .map(|member| member.roles.as_slice())
+            .unwrap_or_default();
+
+        // only the person pressing it needs to know they can't
+        if let Err(denied) = self.permissions.check(verb, Some(game), roles) {
+            let reply = denied.to_string();
+            let data = CreateInteractionResponseMessage::new()
+                .content(&reply)
+                .ephemeral(true);
+            let _ = component
+                .create_response(&ctx.http, CreateInteractionResponse::Message(data))
+                .await;
+            audit::record(
+                &ctx.http,
+                &entry,
+                Outcome::Denied,
+                &reply,
+                started.elapsed(),
+            )
+            .await;
+            return;
+        }
+
+        if confirmed {
+            let reply = run_confirmed(ctx, component, verb, game).await;
+            audit::record(
+                &ctx.http,
+                &entry,
+                Outcome::Completed,
+                &reply,
+                started.elapsed(),
+            )
+            .await;
+            return;
+        }
+
+        // a new message rather than editing the list, so everyone sees who did it
+        let defer = CreateInteractionResponseMessage::new();
+        if let Err(e) = component
+            .create_response(&ctx.http, CreateInteractionResponse::Defer(defer))
+            .await
+        {
+            eprintln!("Failed to defer: {e}");
+            return;
+        }
+
+        let (reply, outcome) = match confirm::ask(verb, game).await {
+            Some(confirmation) => {
+                let _ = component.delete_response(&ctx.http).await;
+                let (followup, content) = confirmation_followup(confirmation);
+                if let Err(e) = component.create_followup(&ctx.http, followup).await {
+                    eprintln!("Failed to ask for confirmation: {e}");
                 }
+                (content, Outcome::AwaitingConfirmation)
             }
-        }
+            None => {
+                let progress = EditInteractionResponse::new().content(format!(
+                    "{} pressed {} on {game}, working on it...",
+                    component.user.name,
+                    verb.name()
+                ));
+                let _ = component.edit_response(&ctx.http, progress).await;
+
+                let reply = commands::list::press(verb, game, &component.user.name).await;
+                let final_msg = EditInteractionResponse::new().content(&reply);
+                let _ = component.edit_response(&ctx.http, final_msg).await;
+                (reply, Outcome::Completed)
+            }
+        };
+
+        audit::record(&ctx.http, &entry, outcome, &reply, started.elapsed()).await;
     }
-}
 
-/// Check to see if the user belongs to the trusted role
-fn is_trusted_member(command: &CommandInteraction) -> bool {
-    if let Some(member) = command.member.as_ref() {
-        member.roles.contains(&tokens::TRUSTED_ROLE_ID)
-    } else {
-        false
+    /// Check the member has a role allowed to use the command on that game
+    fn check_permission(&self, command: &CommandInteraction) -> Result<(), Denied> {
+        let options = command.data.options();
+        let Some(verb) = commands::verb(&command.data.name, &options) else {
+            // unrecognised commands get told so
+            return Ok(());
+        };
+
+        let roles = command
+            .member
+            .as_ref()
+            .map(|member| member.roles.as_slice())
+            .unwrap_or_default();
+
+        self.permissions
+            .check(verb, commands::find_game(&options), roles)
     }
 }
 
-/// For anything very likely to take <3 seconds
-async fn respond_immediately(ctx: &Context, command: &CommandInteraction, content: String) {
-    let data = CreateInteractionResponseMessage::new().content(content);
+/// For anything very likely to take <3 seconds, returning what was sent
+async fn respond_immediately(
+    ctx: &Context,
+    command: &CommandInteraction,
+    content: String,
+) -> String {
+    let data = CreateInteractionResponseMessage::new().content(content.clone());
     let builder = CreateInteractionResponse::Message(data);
     let _ = command.create_response(&ctx.http, builder).await;
+    content
 }
 
 /// For anything that might take >3 seconds, where discord will otherwise assume
 /// failure and not wait for the response
+///
+/// Every game operation has its own timeout, so this always gets a reply in
+/// well before the interaction token expires
 async fn respond_deferred<F>(
     ctx: &Context,
     command: &CommandInteraction,
     initial_message: &str,
-    run_function: F,
-) where
-    F: FnOnce() -> String + Send + 'static,
+    operation: F,
+) -> String
+where
+    F: Future<Output = String>,
 {
     if let Err(e) = command.defer(&ctx.http).await {
         eprintln!("Failed to defer: {e}");
-        return;
+        return format!("Failed to respond: {e}");
     }
 
-    let progress = serenity::builder::EditInteractionResponse::new().content(initial_message);
+    let progress = EditInteractionResponse::new().content(initial_message);
     let _ = command.edit_response(&ctx.http, progress).await;
 
-    let result = tokio::task::spawn_blocking(run_function).await.unwrap();
+    let result = operation.await;
 
-    let final_msg = serenity::builder::EditInteractionResponse::new().content(result);
+    let final_msg = EditInteractionResponse::new().content(result.clone());
     let _ = command.edit_response(&ctx.http, final_msg).await;
+    result
+}
+
+/// Like [respond_deferred], but for verbs that kick players: while anyone is
+/// online the member gets an ephemeral confirmation instead, and the public
+/// reply is removed. The outcome tells the audit log which happened.
+async fn respond_kicking<F>(
+    ctx: &Context,
+    command: &CommandInteraction,
+    verb: Verb,
+    initial_message: &str,
+    operation: F,
+) -> (String, Outcome)
+where
+    F: Future<Output = String>,
+{
+    if let Err(e) = command.defer(&ctx.http).await {
+        eprintln!("Failed to defer: {e}");
+        return (format!("Failed to respond: {e}"), Outcome::Completed);
+    }
+
+    let progress = EditInteractionResponse::new().content("Checking who is online...");
+    let _ = command.edit_response(&ctx.http, progress).await;
+
+    let options = command.data.options();
+    let confirmation = match commands::find_game(&options) {
+        Some(game) => confirm::ask(verb, game).await,
+        None => None,
+    };
+
+    if let Some(confirmation) = confirmation {
+        let _ = command.delete_response(&ctx.http).await;
+        let (followup, content) = confirmation_followup(confirmation);
+        if let Err(e) = command.create_followup(&ctx.http, followup).await {
+            eprintln!("Failed to ask for confirmation: {e}");
+        }
+        return (content, Outcome::AwaitingConfirmation);
+    }
+
+    let progress = EditInteractionResponse::new().content(initial_message);
+    let _ = command.edit_response(&ctx.http, progress).await;
+
+    let result = operation.await;
+
+    let final_msg = EditInteractionResponse::new().content(result.clone());
+    let _ = command.edit_response(&ctx.http, final_msg).await;
+    (result, Outcome::Completed)
+}
+
+/// The ephemeral question with its button, and its text for the audit log
+fn confirmation_followup(
+    confirmation: Confirmation,
+) -> (CreateInteractionResponseFollowup, String) {
+    let followup = CreateInteractionResponseFollowup::new()
+        .content(&confirmation.content)
+        .components(vec![confirmation.button])
+        .ephemeral(true);
+    (followup, confirmation.content)
+}
+
+/// Once confirmed, take the button away and run the verb in a public
+/// message, so everyone sees who did it
+async fn run_confirmed(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    verb: Verb,
+    game: &str,
+) -> String {
+    let confirmed = CreateInteractionResponseMessage::new()
+        .content(format!(
+            "Confirmed, giving the players on {game} time to log off"
+        ))
+        .components(Vec::new());
+    if let Err(e) = component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(confirmed),
+        )
+        .await
+    {
+        eprintln!("Failed to confirm: {e}");
+        return format!("Failed to respond: {e}");
+    }
+
+    let progress = CreateInteractionResponseFollowup::new().content(format!(
+        "{} confirmed {} on {game} with players online, waiting {} seconds for them to log off...",
+        component.user.name,
+        verb.name(),
+        confirm::COUNTDOWN[0]
+    ));
+    let message = match component.create_followup(&ctx.http, progress).await {
+        Ok(message) => Some(message),
+        Err(e) => {
+            eprintln!("Failed to post progress: {e}");
+            None
+        }
+    };
+
+    let reply = confirm::run(verb, game, &component.user.name).await;
+
+    if let Some(message) = message {
+        let final_msg = CreateInteractionResponseFollowup::new().content(&reply);
+        let _ = component
+            .edit_followup(&ctx.http, message.id, final_msg)
+            .await;
+    }
+    reply
+}
+
+/// Like [respond_deferred], but with an embed and buttons per server
+///
+/// Servers past the first [CARDS_PER_MESSAGE] go in follow up messages.
+/// Returns the text summary.
+async fn respond_cards<F>(
+    ctx: &Context,
+    command: &CommandInteraction,
+    initial_message: &str,
+    cards: F,
+) -> String
+where
+    F: Future<Output = Vec<Card>>,
+{
+    if let Err(e) = command.defer(&ctx.http).await {
+        eprintln!("Failed to defer: {e}");
+        return format!("Failed to respond: {e}");
+    }
+
+    let progress = EditInteractionResponse::new().content(initial_message);
+    let _ = command.edit_response(&ctx.http, progress).await;
+
+    let mut cards = cards.await;
+    if cards.is_empty() {
+        let message = "There are no servers to list".to_string();
+        let final_msg = EditInteractionResponse::new().content(&message);
+        let _ = command.edit_response(&ctx.http, final_msg).await;
+        return message;
+    }
+
+    let summary: Vec<String> = cards.iter().map(|card| card.summary.clone()).collect();
+
+    let rest = cards.split_off(CARDS_PER_MESSAGE.min(cards.len()));
+    let (embeds, buttons): (Vec<_>, Vec<_>) = cards
+        .into_iter()
+        .map(|card| (card.embed, card.buttons))
+        .unzip();
+    let first = EditInteractionResponse::new()
+        .content("## List of brumders servers")
+        .embeds(embeds)
+        .components(buttons);
+    let _ = command.edit_response(&ctx.http, first).await;
+
+    let mut rest = rest.into_iter().peekable();
+    while rest.peek().is_some() {
+        let (embeds, buttons): (Vec<_>, Vec<_>) = rest
+            .by_ref()
+            .take(CARDS_PER_MESSAGE)
+            .map(|card| (card.embed, card.buttons))
+            .unzip();
+        let followup = CreateInteractionResponseFollowup::new()
+            .embeds(embeds)
+            .components(buttons);
+        if let Err(e) = command.create_followup(&ctx.http, followup).await {
+            eprintln!("Failed to post more servers: {e}");
+        }
+    }
+
+    summary.join("\n")
 }
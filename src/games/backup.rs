@@ -0,0 +1,343 @@
+use crate::config::BackupConfig;
+use crate::games::{CommandOutput, CommandRunner, GameServerError, STATUS_TIMEOUT};
+
+use chrono::Local;
+use serenity::async_trait;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Lists backup archives as `<modified epoch> <size> <file name>` lines
+const LIST_BACKUPS: &str = "find \"$1\" -maxdepth 1 -type f -name '*.tar*' -printf '%T@ %s %f\\n'";
+
+/// A backup archive on the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    /// File name of the archive, used to pick it for a restore
+    pub id: String,
+    pub size: u64,
+    pub created: SystemTime,
+}
+
+/// Somewhere a server's saves can be copied to and restored from
+///
+/// The server is always stopped before a restore, so implementations only
+/// need to deal with files
+#[async_trait]
+pub trait Backups: Send + Sync {
+    async fn create(&self, timeout: Duration) -> Result<(), GameServerError>;
+    /// Every backup, newest first
+    async fn list(&self) -> Result<Vec<BackupInfo>, GameServerError>;
+    async fn restore(&self, id: &str, timeout: Duration) -> Result<(), GameServerError>;
+}
+
+/// Backups made by LGSM itself, kept in `~/lgsm/backup`
+pub struct LgsmBackups {
+    user: String,
+    runner: String,
+    host: Arc<dyn CommandRunner>,
+}
+
+impl LgsmBackups {
+    pub fn new(user: &str, runner: &str, host: Arc<dyn CommandRunner>) -> Self {
+        LgsmBackups {
+            user: user.to_string(),
+            runner: runner.to_string(),
+            host,
+        }
+    }
+
+    /// Run a shell script as the server user, failing on a non-zero exit
+    async fn su(
+        &self,
+        label: &str,
+        script: &str,
+        timeout: Duration,
+    ) -> Result<CommandOutput, GameServerError> {
+        self.host
+            .run("su", &["-", &self.user, "-c", script], timeout)
+            .await?
+            .check(&format!("{} {label}", self.runner))
+    }
+}
+
+#[async_trait]
+impl Backups for LgsmBackups {
+    async fn create(&self, timeout: Duration) -> Result<(), GameServerError> {
+        let script = format!("/home/{}/{} backup", self.user, self.runner);
+        self.su("backup", &script, timeout).await.map(|_| ())
+    }
+
+    async fn list(&self) -> Result<Vec<BackupInfo>, GameServerError> {
+        let script = format!(
+            "set -- /home/{}/lgsm/backup\n[ -d \"$1\" ] || exit 0\n{LIST_BACKUPS}",
+            self.user
+        );
+        let output = self.su("list backups", &script, STATUS_TIMEOUT).await?;
+        Ok(parse_listing(&output.stdout))
+    }
+
+    /// LGSM archives the whole home directory, relative to it
+    async fn restore(&self, id: &str, timeout: Duration) -> Result<(), GameServerError> {
+        let script = format!(
+            "tar -xf /home/{user}/lgsm/backup/{id} -C /home/{user}",
+            user = self.user
+        );
+        self.su("restore", &script, timeout).await.map(|_| ())
+    }
+}
+
+/// Timestamped tarballs of a save directory, for servers without LGSM
+pub struct ArchiveBackups {
+    /// Name of the server, used in archive names
+    name: String,
+    user: String,
+    config: BackupConfig,
+    host: Arc<dyn CommandRunner>,
+}
+
+impl ArchiveBackups {
+    pub fn new(name: &str, user: &str, config: BackupConfig, host: Arc<dyn CommandRunner>) -> Self {
+        ArchiveBackups {
+            name: name.to_string(),
+            user: user.to_string(),
+            config,
+            host,
+        }
+    }
+
+    /// Run a shell script as the server user, with `args` as `$1`, `$2`...
+    /// so paths never need quoting
+    async fn sh(
+        &self,
+        label: &str,
+        script: &str,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<CommandOutput, GameServerError> {
+        let mut sudo = vec!["-u", &self.user, "sh", "-c", script, label];
+        sudo.extend_from_slice(args);
+        self.host
+            .run("sudo", &sudo, timeout)
+            .await?
+            .check(&format!("{label} {}", self.name))
+    }
+
+    /// The save directory split into its parent and its own name
+    fn save_parts(&self) -> (String, String) {
+        let path = Path::new(&self.config.path);
+        let parent = path.parent().unwrap_or(Path::new("/"));
+        let name = path.file_name().unwrap_or_default();
+        (
+            parent.to_string_lossy().into_owned(),
+            name.to_string_lossy().into_owned(),
+        )
+    }
+}
+
+#[async_trait]
+impl Backups for ArchiveBackups {
+    async fn create(&self, timeout: Duration) -> Result<(), GameServerError> {
+        let (parent, save) = self.save_parts();
+        let archive = format!(
+            "{}-{}.tar.gz",
+            self.name,
+            Local::now().format("%Y%m%d-%H%M%S")
+        );
+
+        self.sh(
+            "backup",
+            "mkdir -p \"$1\" && tar -czf \"$1/$2\" -C \"$3\" \"$4\"",
+            &[&self.config.dir, &archive, &parent, &save],
+            timeout,
+        )
+        .await?;
+
+        // retention only ever touches archives this server made
+        let prefix = format!("{}-", self.name);
+        let expired: Vec<String> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|backup| backup.id.starts_with(&prefix))
+            .skip(self.config.keep)
+            .map(|backup| backup.id)
+            .collect();
+
+        if !expired.is_empty() {
+            let mut args = vec![self.config.dir.as_str()];
+            args.extend(expired.iter().map(String::as_str));
+            self.sh(
+                "prune",
+                "cd \"$1\" && shift && rm -f -- \"$@\"",
+                &args,
+                STATUS_TIMEOUT,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<BackupInfo>, GameServerError> {
+        let script = format!("[ -d \"$1\" ] || exit 0\n{LIST_BACKUPS}");
+        let output = self
+            .sh("list", &script, &[&self.config.dir], STATUS_TIMEOUT)
+            .await?;
+        Ok(parse_listing(&output.stdout))
+    }
+
+    /// The current save is kept next to it as `<save>.pre-restore`, in case
+    /// the wrong backup was picked
+    async fn restore(&self, id: &str, timeout: Duration) -> Result<(), GameServerError> {
+        let (parent, save) = self.save_parts();
+        let archive = format!("{}/{id}", self.config.dir);
+
+        self.sh(
+            "restore",
+            "cd \"$1\" && rm -rf -- \"$2.pre-restore\" \
+            && { [ ! -e \"$2\" ] || mv -- \"$2\" \"$2.pre-restore\"; } \
+            && tar -xzf \"$3\"",
+            &[&parent, &save, &archive],
+            timeout,
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// Parse the output of [LIST_BACKUPS], newest first
+///
+/// Anything with an unusual file name is skipped, since ids end up in shell
+/// commands
+fn parse_listing(stdout: &str) -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let modified: f64 = fields.next()?.parse().ok()?;
+            let size = fields.next()?.parse().ok()?;
+            let id = fields.next()?.to_string();
+
+            let safe = id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+            if !safe || id.starts_with('.') {
+                return None;
+            }
+
+            Some(BackupInfo {
+                id,
+                size,
+                created: UNIX_EPOCH + Duration::from_secs(modified as u64),
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+    backups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ScriptedRunner;
+
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    fn archives(host: &Arc<ScriptedRunner>, keep: usize) -> ArchiveBackups {
+        let config = BackupConfig {
+            path: "/home/gs_enshrouded/serverfiles/savegame".to_string(),
+            dir: "/home/gs_enshrouded/backups".to_string(),
+            keep,
+        };
+        ArchiveBackups::new("enshrouded", "gs_enshrouded", config, host.clone())
+    }
+
+    #[test]
+    fn listings_are_newest_first_and_skip_odd_names() {
+        let backups = parse_listing(
+            "1700000000.5 1024 enshrouded-20231114-221320.tar.gz\n\
+             1700090000.0 2048 enshrouded-20231115-230000.tar.gz\n\
+             1700000001.0 10 bad name;rm -rf.tar.gz\n\
+             garbage\n",
+        );
+
+        let ids: Vec<_> = backups.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "enshrouded-20231115-230000.tar.gz",
+                "enshrouded-20231114-221320.tar.gz"
+            ]
+        );
+        assert_eq!(backups[0].size, 2048);
+    }
+
+    #[tokio::test]
+    async fn archives_past_the_retention_limit_are_pruned() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .exits(0)
+                .prints(
+                    0,
+                    "3 10 enshrouded-3.tar.gz\n\
+                     1 10 enshrouded-1.tar.gz\n\
+                     2 10 enshrouded-2.tar.gz\n\
+                     0 10 other-0.tar.gz\n",
+                )
+                .exits(0),
+        );
+
+        archives(&host, 2).create(LIMIT).await.unwrap();
+
+        let calls = host.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls[0].contains("tar -czf"));
+        assert!(calls[0].ends_with("/home/gs_enshrouded/serverfiles savegame"));
+        assert!(calls[2].ends_with("/home/gs_enshrouded/backups enshrouded-1.tar.gz"));
+    }
+
+    #[tokio::test]
+    async fn nothing_is_pruned_within_the_limit() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .exits(0)
+                .prints(0, "1 10 enshrouded-1.tar.gz\n"),
+        );
+
+        archives(&host, 2).create(LIMIT).await.unwrap();
+
+        assert_eq!(host.calls().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_archives_are_reported() {
+        let host = Arc::new(ScriptedRunner::new().exits(2));
+
+        let result = archives(&host, 2).create(LIMIT).await;
+
+        assert!(matches!(
+            result,
+            Err(GameServerError::NonZeroExit { code: Some(2), .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn lgsm_restores_into_the_home_directory() {
+        let host = Arc::new(ScriptedRunner::new().exits(0));
+        let backups = LgsmBackups::new("gs_skyvault", "mcserver", host.clone());
+
+        backups
+            .restore("mcserver-2025-03-01-050000.tar.zst", LIMIT)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            host.calls(),
+            ["su - gs_skyvault -c tar -xf /home/gs_skyvault/lgsm/backup/\
+              mcserver-2025-03-01-050000.tar.zst -C /home/gs_skyvault"]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::rcon::RconError;
+
+use std::time::Duration;
+use std::{fmt, io};
+
+/// What a successful operation actually did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationOutcome {
+    Started,
+    AlreadyRunning,
+    Stopped,
+    /// Killed after not exiting when asked to
+    ForceStopped,
+    AlreadyStopped,
+    Restarted,
+    /// Killed after not exiting when asked to, then started again
+    ForceRestarted,
+    /// Steam build ids before and after, when the backend knows them
+    Updated {
+        from: Option<u64>,
+        to: Option<u64>,
+    },
+    /// The installed steam build, when the backend knows it
+    UpToDate(Option<u64>),
+    BackedUp,
+    Restored,
+}
+
+/// Short name for the history, with the builds of an update
+impl fmt::Display for OperationOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationOutcome::Updated {
+                from: Some(from),
+                to: Some(to),
+            } => write!(f, "Updated {from} → {to}"),
+            OperationOutcome::Updated { to: Some(to), .. } => write!(f, "Updated to build {to}"),
+            OperationOutcome::Updated { .. } => write!(f, "Updated"),
+            OperationOutcome::UpToDate(Some(build)) => write!(f, "UpToDate (build {build})"),
+            OperationOutcome::UpToDate(None) => write!(f, "UpToDate"),
+            _ => write!(f, "{self:?}"),
+        }
+    }
+}
+
+/// Everything that can go wrong while managing a game server
+#[derive(Debug)]
+pub enum GameServerError {
+    /// The host command could not be started at all
+    CommandSpawnFailed { command: String, source: io::Error },
+    /// The host command ran but reported a failure
+    NonZeroExit {
+        command: String,
+        code: Option<i32>,
+        stderr: String,
+    },
+    /// The host command took too long and was killed
+    Timeout { command: String, limit: Duration },
+    /// The backend has no way of doing this from discord
+    NotSupported(&'static str),
+    /// The console command is not on the server's allowlist
+    NotAllowed(String),
+    /// The in-game console did not answer
+    Rcon(RconError),
+    /// Refused because it would kick this many players
+    PlayersOnline(u32),
+    /// Refused because it would change files under the running server
+    ServerRunning,
+    /// There is no backup with this id
+    UnknownBackup(String),
+}
+
+impl GameServerError {
+    /// Build a [GameServerError::NonZeroExit] from whatever the command printed
+    pub fn non_zero(command: impl Into<String>, code: Option<i32>, stderr: &str) -> Self {
+        GameServerError::NonZeroExit {
+            command: command.into(),
+            code,
+            stderr: stderr.trim().to_string(),
+        }
+    }
+
+    /// Everything worth knowing about the failure, for the logs
+    pub fn details(&self) -> String {
+        match self {
+            GameServerError::NonZeroExit {
+                command,
+                code,
+                stderr,
+            } => format!("`{command}` exited with {code:?}: {stderr}"),
+            GameServerError::Timeout { command, limit } => {
+                format!("`{command}` killed after {}s", limit.as_secs())
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GameServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameServerError::CommandSpawnFailed { command, source } => {
+                write!(f, "could not run `{command}`: {source}")
+            }
+            GameServerError::NonZeroExit {
+                command,
+                code: Some(code),
+                ..
+            } => write!(f, "`{command}` exited with code {code}"),
+            GameServerError::NonZeroExit { command, .. } => {
+                write!(f, "`{command}` was killed by a signal")
+            }
+            GameServerError::Timeout { limit, .. } => {
+                let minutes = limit.as_secs().div_ceil(60);
+                let plural = if minutes == 1 { "" } else { "s" };
+                write!(f, "timed out after {minutes} minute{plural}")
+            }
+            GameServerError::NotSupported(reason) => write!(f, "{reason}"),
+            GameServerError::NotAllowed(command) => {
+                write!(f, "`{command}` is not an allowed console command")
+            }
+            GameServerError::Rcon(e) => write!(f, "RCON failed: {e}"),
+            GameServerError::PlayersOnline(players) => {
+                let plural = if *players == 1 {
+                    "player is"
+                } else {
+                    "players are"
+                };
+                write!(f, "{players} {plural} online")
+            }
+            GameServerError::UnknownBackup(id) => write!(f, "there is no backup called `{id}`"),
+            GameServerError::ServerRunning => write!(f, "the server is running"),
+        }
+    }
+}
+
+impl std::error::Error for GameServerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GameServerError::CommandSpawnFailed { source, .. } => Some(source),
+            GameServerError::Rcon(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Result of any operation on a game server
+pub type OperationResult = Result<OperationOutcome, GameServerError>;
@@ -0,0 +1,599 @@
+use crate::config::{RconConfig, ServerConfig};
+use crate::games::{
+    Backend, BackupInfo, Backups, GameServer, GameServerError, OperationOutcome, OperationResult,
+    ServerStatus, StatusReport, UpdateCheck, VersionCommands,
+};
+use crate::ip;
+use crate::rcon::{self, RCON_TIMEOUT};
+use crate::store;
+
+use serenity::async_trait;
+
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often player counts are written to the history
+const PLAYER_SAMPLE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long looking for an update may take, steamcmd can be slow to log in
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A game server assembled from its config metadata and a backend
+pub struct Game {
+    /// Metadata declared in the config
+    config: ServerConfig,
+    /// Whatever actually runs the server on the host
+    backend: Box<dyn Backend>,
+    /// Where saves are backed up to, if anywhere
+    backups: Option<Box<dyn Backups>>,
+    /// Commands from the config that check for updates instead of the backend
+    versions: Option<VersionCommands>,
+    /// State the backend can not see for itself
+    tracking: Mutex<Tracking>,
+}
+
+/// What the bot knows about a server beyond what the backend reports
+#[derive(Default)]
+struct Tracking {
+    /// Operation currently running, e.g. [ServerStatus::Updating]
+    activity: Option<ServerStatus>,
+    /// Last status seen and when it was first seen
+    last_seen: Option<(ServerStatus, SystemTime)>,
+    /// What the last start or stop asked for
+    expected: Option<ServerStatus>,
+    /// When player counts were last written to the history
+    last_sample: Option<SystemTime>,
+    /// The last full status report and when it was made
+    last_report: Option<(StatusReport, Instant)>,
+}
+
+/// Clears the in-progress activity once an operation finishes
+struct ActivityGuard<'a>(&'a Mutex<Tracking>);
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().activity = None;
+    }
+}
+
+impl Game {
+    pub fn new(
+        config: ServerConfig,
+        backend: Box<dyn Backend>,
+        backups: Option<Box<dyn Backups>>,
+        versions: Option<VersionCommands>,
+    ) -> Self {
+        Game {
+            config,
+            backend,
+            backups,
+            versions,
+            tracking: Mutex::new(Tracking::default()),
+        }
+    }
+
+    /// Record what the server should be doing once the operation is done
+    fn expect(&self, status: ServerStatus) {
+        self.tracking.lock().unwrap().expected = Some(status);
+    }
+
+    fn backup_store(&self) -> Result<&dyn Backups, GameServerError> {
+        self.backups
+            .as_deref()
+            .ok_or(GameServerError::NotSupported("has no backups set up"))
+    }
+
+    /// Run any command on the RCON console, allowed or not
+    async fn rcon(&self, rcon: &RconConfig, command: &str) -> Result<String, GameServerError> {
+        let password = env::var(&rcon.password_env)
+            .map_err(|_| GameServerError::NotSupported("has no RCON password set"))?;
+
+        rcon::execute(&rcon.host, rcon.port, &password, command, RCON_TIMEOUT)
+            .await
+            .map_err(GameServerError::Rcon)
+    }
+
+    /// Mark an operation as in progress until the guard is dropped
+    fn begin(&self, activity: ServerStatus) -> ActivityGuard<'_> {
+        let mut tracking = self.tracking.lock().unwrap();
+        self.transition(&mut tracking, activity.clone(), SystemTime::now());
+        tracking.activity = Some(activity);
+        ActivityGuard(&self.tracking)
+    }
+
+    /// The last full status report and how old it is
+    fn last_report(&self) -> Option<(StatusReport, Duration)> {
+        let tracking = self.tracking.lock().unwrap();
+        tracking.last_report.as_ref().map(|(report, at)| {
+            // an operation started since is still worth knowing about
+            let mut report = report.clone();
+            if let Some(activity) = &tracking.activity {
+                report.status = activity.clone();
+            }
+            (report, at.elapsed())
+        })
+    }
+
+    /// Remember a new status, and write it to the history
+    fn transition(&self, tracking: &mut Tracking, status: ServerStatus, since: SystemTime) {
+        store::record("status change", |store| {
+            store.record_transition(self.name(), &status, SystemTime::now())
+        });
+        tracking.last_seen = Some((status, since));
+    }
+}
+
+/// Convert a configured number of minutes into a timeout
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
+
+#[async_trait]
+impl GameServer for Game {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn description(&self) -> &str {
+        &self.config.description
+    }
+
+    fn port(&self) -> u16 {
+        self.config.port
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        self.config.idle_timeout.map(minutes)
+    }
+
+    async fn help_message(&self) -> String {
+        let mut help = self
+            .config
+            .help
+            .replace("{ip}", &ip::public_ip())
+            .replace("{port}", &self.port().to_string());
+
+        if self.config.query.is_some() {
+            let report = self.status().await;
+            help.push_str(&format!("\n### Live status\n{}\n", report.status));
+            if let Some(info) = &report.info {
+                help.push_str(&info.details());
+            }
+        }
+
+        help
+    }
+
+    async fn start(&self) -> OperationResult {
+        self.expect(ServerStatus::Running);
+        let _activity = self.begin(ServerStatus::Starting);
+        self.backend
+            .start(minutes(self.config.timeouts.start))
+            .await
+    }
+
+    async fn stop(&self) -> OperationResult {
+        self.expect(ServerStatus::Stopped);
+        let _activity = self.begin(ServerStatus::Stopping);
+        self.backend.stop(minutes(self.config.timeouts.stop)).await
+    }
+
+    async fn restart(&self) -> OperationResult {
+        self.expect(ServerStatus::Running);
+        let _activity = self.begin(ServerStatus::Starting);
+        self.backend
+            .restart(minutes(self.config.timeouts.restart))
+            .await
+    }
+
+    async fn update(&self) -> OperationResult {
+        if self.config.manual_update {
+            return Err(GameServerError::NotSupported("must be updated manually"));
+        }
+
+        let _activity = self.begin(ServerStatus::Updating);
+        self.backend
+            .update(minutes(self.config.timeouts.update))
+            .await
+    }
+
+    async fn can_update(&self) -> Result<(), GameServerError> {
+        if self.config.manual_update {
+            return Err(GameServerError::NotSupported("must be updated manually"));
+        }
+
+        self.backend.can_update().await
+    }
+
+    async fn check_update(&self) -> Result<UpdateCheck, GameServerError> {
+        match &self.versions {
+            Some(versions) => versions.check(UPDATE_CHECK_TIMEOUT).await,
+            None => self.backend.check_update(UPDATE_CHECK_TIMEOUT).await,
+        }
+    }
+
+    async fn backup(&self) -> OperationResult {
+        let backups = self.backup_store()?;
+
+        let _activity = self.begin(ServerStatus::BackingUp);
+        backups.create(minutes(self.config.timeouts.backup)).await?;
+        Ok(OperationOutcome::BackedUp)
+    }
+
+    async fn backups(&self) -> Result<Vec<BackupInfo>, GameServerError> {
+        self.backup_store()?.list().await
+    }
+
+    /// Stops the server first, and starts it again afterwards if it was up.
+    /// It is expected to stay stopped unless that start works, so the
+    /// watchdog never brings it back on a half restored save.
+    async fn restore(&self, id: &str) -> OperationResult {
+        let backups = self.backup_store()?;
+
+        if !backups.list().await?.iter().any(|backup| backup.id == id) {
+            return Err(GameServerError::UnknownBackup(id.to_string()));
+        }
+
+        let report = self.status().await;
+        let was_up = report.status.is_up();
+        if was_up {
+            match &report.info {
+                Some(info) if info.players > 0 => {
+                    return Err(GameServerError::PlayersOnline(info.players));
+                }
+                Some(_) => {}
+                None => {
+                    return Err(GameServerError::NotSupported(
+                        "can not tell who is online, stop it before restoring",
+                    ));
+                }
+            }
+        }
+
+        let _activity = self.begin(ServerStatus::Restoring);
+        let timeout = minutes(self.config.timeouts.backup);
+
+        if was_up {
+            self.expect(ServerStatus::Stopped);
+            self.backend
+                .stop(minutes(self.config.timeouts.stop))
+                .await?;
+        }
+
+        backups.restore(id, timeout).await?;
+
+        if was_up {
+            self.backend
+                .start(minutes(self.config.timeouts.start))
+                .await?;
+            self.expect(ServerStatus::Running);
+        }
+
+        Ok(OperationOutcome::Restored)
+    }
+
+    async fn status(&self) -> StatusReport {
+        let mut report = self.backend.status().await;
+
+        if let Some(query) = self.config.query.as_ref().filter(|_| report.status.is_up()) {
+            match crate::query::query(query, self.port()).await {
+                Ok(info) => {
+                    // the process being up is not enough, it is only running once it answers
+                    report.status = ServerStatus::Running;
+                    report.info = Some(info);
+                }
+                Err(e) => {
+                    eprintln!("{} did not answer its status query: {e}", self.name());
+                    let loading = report
+                        .uptime
+                        .is_none_or(|uptime| uptime < minutes(query.startup_grace));
+                    report.status = if loading {
+                        ServerStatus::Starting
+                    } else {
+                        ServerStatus::Unknown(format!("running but not answering queries: {e}"))
+                    };
+                }
+            }
+        }
+
+        let mut tracking = self.tracking.lock().unwrap();
+
+        // the bot knows better than the backend while it is mid-operation
+        if let Some(activity) = &tracking.activity {
+            report.status = activity.clone();
+        }
+
+        let changed = match &tracking.last_seen {
+            Some((status, _)) => *status != report.status,
+            None => true,
+        };
+
+        if changed {
+            let since = report.since.unwrap_or_else(SystemTime::now);
+            self.transition(&mut tracking, report.status.clone(), since);
+        }
+
+        if let Some(info) = &report.info {
+            let now = SystemTime::now();
+            let due = tracking.last_sample.is_none_or(|last| {
+                now.duration_since(last).unwrap_or_default() >= PLAYER_SAMPLE_INTERVAL
+            });
+
+            if due {
+                tracking.last_sample = Some(now);
+                store::record("player count", |store| {
+                    store.record_players(self.name(), info.players, info.max_players, now)
+                });
+            }
+        }
+
+        report.since = tracking.last_seen.as_ref().map(|(_, since)| *since);
+        tracking.last_report = Some((report.clone(), Instant::now()));
+        report
+    }
+
+    async fn recent_status(&self, max_age: Duration) -> StatusReport {
+        match self.last_report().filter(|(_, age)| *age < max_age) {
+            Some((report, _)) => report,
+            None => self.status().await,
+        }
+    }
+
+    fn cached_status(&self) -> Option<StatusReport> {
+        self.last_report().map(|(report, _)| report)
+    }
+
+    fn expected_status(&self) -> Option<ServerStatus> {
+        self.tracking.lock().unwrap().expected.clone()
+    }
+
+    fn console_commands(&self) -> &[String] {
+        self.config
+            .rcon
+            .as_ref()
+            .map(|rcon| rcon.allow.as_slice())
+            .unwrap_or_default()
+    }
+
+    async fn console(&self, command: &str) -> Result<String, GameServerError> {
+        let rcon = self
+            .config
+            .rcon
+            .as_ref()
+            .ok_or(GameServerError::NotSupported("has no RCON console"))?;
+
+        // players are used to typing minecraft commands with a slash
+        let command = command.trim().trim_start_matches('/');
+        let name = command.split_whitespace().next().unwrap_or_default();
+        if !rcon
+            .allow
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+        {
+            return Err(GameServerError::NotAllowed(name.to_string()));
+        }
+
+        self.rcon(rcon, command).await
+    }
+
+    async fn broadcast(&self, message: &str) -> Result<(), GameServerError> {
+        let (rcon, template) = self
+            .config
+            .rcon
+            .as_ref()
+            .and_then(|rcon| Some((rcon, rcon.broadcast.as_ref()?)))
+            .ok_or(GameServerError::NotSupported("can not broadcast in-game"))?;
+
+        let command = template.replace("{message}", message);
+        self.rcon(rcon, &command).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::{LgsmBackend, ScriptedRunner};
+    use crate::query::ServerInfo;
+
+    use std::sync::Arc;
+
+    /// Everything the fakes were asked to do, in order
+    type Calls = Arc<Mutex<Vec<&'static str>>>;
+
+    /// Backend for a running server with `players` online, or that can not
+    /// count them at all without
+    struct FakeBackend {
+        players: Option<u32>,
+        calls: Calls,
+    }
+
+    impl FakeBackend {
+        fn call(&self, name: &'static str) {
+            self.calls.lock().unwrap().push(name);
+        }
+    }
+
+    #[async_trait]
+    impl Backend for FakeBackend {
+        async fn start(&self, _timeout: Duration) -> OperationResult {
+            self.call("start");
+            Ok(OperationOutcome::Started)
+        }
+
+        async fn stop(&self, _timeout: Duration) -> OperationResult {
+            self.call("stop");
+            Ok(OperationOutcome::Stopped)
+        }
+
+        async fn restart(&self, _timeout: Duration) -> OperationResult {
+            self.call("restart");
+            Ok(OperationOutcome::Restarted)
+        }
+
+        async fn update(&self, _timeout: Duration) -> OperationResult {
+            self.call("update");
+            Ok(OperationOutcome::UpToDate(None))
+        }
+
+        async fn status(&self) -> StatusReport {
+            self.call("status");
+            let mut report = StatusReport::new(ServerStatus::Running);
+            report.info = self.players.map(|players| ServerInfo {
+                players,
+                max_players: 8,
+                ..Default::default()
+            });
+            report
+        }
+    }
+
+    /// A single backup called `nightly`, which can fail to restore
+    struct FakeBackups {
+        fails: bool,
+        calls: Calls,
+    }
+
+    #[async_trait]
+    impl Backups for FakeBackups {
+        async fn create(&self, _timeout: Duration) -> Result<(), GameServerError> {
+            self.calls.lock().unwrap().push("backup");
+            Ok(())
+        }
+
+        async fn list(&self) -> Result<Vec<BackupInfo>, GameServerError> {
+            Ok(vec![BackupInfo {
+                id: "nightly".to_string(),
+                size: 1024,
+                created: SystemTime::now(),
+            }])
+        }
+
+        async fn restore(&self, _id: &str, _timeout: Duration) -> Result<(), GameServerError> {
+            self.calls.lock().unwrap().push("restore");
+            if self.fails {
+                Err(GameServerError::non_zero("tar -xzf", Some(2), "disk full"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// A running server with backups, and the log of what it was asked to do
+    fn restorable(players: Option<u32>, fails: bool) -> (Game, Calls) {
+        let calls = Calls::default();
+        let backend = FakeBackend {
+            players,
+            calls: calls.clone(),
+        };
+        let backups = FakeBackups {
+            fails,
+            calls: calls.clone(),
+        };
+        let game = Game::new(config(""), Box::new(backend), Some(Box::new(backups)), None);
+        (game, calls)
+    }
+
+    /// A server declared with `extra` keys, e.g. an `rcon` table
+    fn config(extra: &str) -> ServerConfig {
+        toml::from_str(&format!(
+            r#"
+            name        = "zomboid"
+            description = "Project Zomboid"
+            port        = 16261
+            user        = "gs_zomboid"
+            help        = "help"
+            backend     = {{ kind = "lgsm", runner = "pzserver" }}
+            {extra}
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn console_refuses_commands_outside_the_allowlist() {
+        let rcon =
+            r#"rcon = { port = 27015, password_env = "ZOMBOID_RCON", allow = ["save", "kick"] }"#;
+        // no scripted responses, so anything reaching the host panics
+        let host = Arc::new(ScriptedRunner::new());
+        let backend = LgsmBackend::new("gs_zomboid", "pzserver", host);
+        let game = Game::new(config(rcon), Box::new(backend), None, None);
+
+        assert!(matches!(
+            game.console("/quit").await,
+            Err(GameServerError::NotAllowed(name)) if name == "quit"
+        ));
+        assert!(matches!(
+            game.console("servermsg kick everyone").await,
+            Err(GameServerError::NotAllowed(name)) if name == "servermsg"
+        ));
+    }
+
+    #[tokio::test]
+    async fn recent_statuses_come_from_the_cache() {
+        let (game, calls) = restorable(Some(0), false);
+        assert!(game.cached_status().is_none());
+
+        let minute = Duration::from_secs(60);
+        game.recent_status(minute).await;
+        game.recent_status(minute).await;
+        assert_eq!(*calls.lock().unwrap(), ["status"]);
+
+        // too old, so the host is asked again
+        game.recent_status(Duration::ZERO).await;
+        assert_eq!(*calls.lock().unwrap(), ["status", "status"]);
+
+        let _activity = game.begin(ServerStatus::Restoring);
+        assert_eq!(
+            game.cached_status().unwrap().status,
+            ServerStatus::Restoring
+        );
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn restore_refuses_while_players_are_online() {
+        let (game, calls) = restorable(Some(2), false);
+
+        assert!(matches!(
+            game.restore("nightly").await,
+            Err(GameServerError::PlayersOnline(2))
+        ));
+        assert_eq!(*calls.lock().unwrap(), ["status"]);
+    }
+
+    #[tokio::test]
+    async fn restore_refuses_when_nobody_can_be_counted() {
+        let (game, calls) = restorable(None, false);
+
+        assert!(matches!(
+            game.restore("nightly").await,
+            Err(GameServerError::NotSupported(_))
+        ));
+        assert_eq!(*calls.lock().unwrap(), ["status"]);
+    }
+
+    #[tokio::test]
+    async fn restore_stops_restores_then_starts() {
+        let (game, calls) = restorable(Some(0), false);
+
+        assert_eq!(
+            game.restore("nightly").await.unwrap(),
+            OperationOutcome::Restored
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["status", "stop", "restore", "start"]
+        );
+        assert_eq!(game.expected_status(), Some(ServerStatus::Running));
+    }
+
+    #[tokio::test]
+    async fn a_failed_restore_is_expected_to_stay_stopped() {
+        let (game, calls) = restorable(Some(0), true);
+
+        assert!(game.restore("nightly").await.is_err());
+        assert_eq!(*calls.lock().unwrap(), ["status", "stop", "restore"]);
+        assert_eq!(game.expected_status(), Some(ServerStatus::Stopped));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::games::{
+    Backend, CommandOutput, CommandRunner, GameServerError, OperationOutcome, OperationResult,
+    STATUS_TIMEOUT, ServerStatus, StatusReport, UpdateCheck,
+};
+
+use serenity::async_trait;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Backend for servers managed by a Linux Game Server Manager (LGSM) script
+pub struct LgsmBackend {
+    /// User that runs the game server
+    user: String,
+    /// LGSM script in the user's home directory, e.g. `sfserver`
+    runner: String,
+    /// Runs the commands on the host
+    host: Arc<dyn CommandRunner>,
+}
+
+impl LgsmBackend {
+    pub fn new(user: &str, runner: &str, host: Arc<dyn CommandRunner>) -> Self {
+        LgsmBackend {
+            user: user.to_string(),
+            runner: runner.to_string(),
+            host,
+        }
+    }
+
+    /// Run a shell command as the server user
+    async fn su(&self, command: &str, timeout: Duration) -> Result<CommandOutput, GameServerError> {
+        self.host
+            .run("su", &["-", &self.user, "-c", command], timeout)
+            .await
+    }
+
+    /// Run the LGSM script as the server user, e.g. `sfserver start`
+    ///
+    /// LGSM exits with 2 when the server is already in the requested state,
+    /// which is only meaningful for some verbs
+    async fn lgsm(
+        &self,
+        verb: &str,
+        timeout: Duration,
+        done: OperationOutcome,
+        already: Option<OperationOutcome>,
+    ) -> OperationResult {
+        let script = format!("/home/{}/{} {}", self.user, self.runner, verb);
+        let output = self.su(&script, timeout).await?;
+
+        match (output.code, already) {
+            // LGSM still exits with 0 when there was nothing to update
+            (Some(0), _) if verb == "update" && output.stdout.contains("No update available") => {
+                Ok(OperationOutcome::UpToDate(None))
+            }
+            (Some(0), _) => Ok(done),
+            (Some(2), Some(already)) => Ok(already),
+            (code, _) => Err(GameServerError::non_zero(
+                format!("{} {verb}", self.runner),
+                code,
+                &output.stderr,
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for LgsmBackend {
+    async fn start(&self, timeout: Duration) -> OperationResult {
+        let already = Some(OperationOutcome::AlreadyRunning);
+        self.lgsm("start", timeout, OperationOutcome::Started, already)
+            .await
+    }
+
+    async fn stop(&self, timeout: Duration) -> OperationResult {
+        let already = Some(OperationOutcome::AlreadyStopped);
+        self.lgsm("stop", timeout, OperationOutcome::Stopped, already)
+            .await
+    }
+
+    async fn restart(&self, timeout: Duration) -> OperationResult {
+        self.lgsm("restart", timeout, OperationOutcome::Restarted, None)
+            .await
+    }
+
+    async fn update(&self, timeout: Duration) -> OperationResult {
+        let updated = OperationOutcome::Updated {
+            from: None,
+            to: None,
+        };
+        self.lgsm("update", timeout, updated, None).await
+    }
+
+    /// LGSM's `check-update` prints both builds, in colour
+    async fn check_update(&self, timeout: Duration) -> Result<UpdateCheck, GameServerError> {
+        let script = format!("/home/{}/{} check-update", self.user, self.runner);
+        let output = self
+            .su(&script, timeout)
+            .await?
+            .check(&format!("{} check-update", self.runner))?;
+
+        Ok(parse_check_update(&output.stdout))
+    }
+
+    /// Checks the LGSM lock file and the tmux session LGSM runs the server in
+    ///
+    /// Newer LGSM versions put the session on its own socket, named after the
+    /// runner and the uid stored in `lgsm/data`
+    async fn status(&self) -> StatusReport {
+        let check = format!(
+            "cd /home/{user} || exit 1\n\
+            lock=lgsm/lock/{runner}-started.lock\n\
+            socket={runner}\n\
+            [ -s lgsm/data/{runner}.uid ] && socket=\"{runner}-$(cat lgsm/data/{runner}.uid)\"\n\
+            if [ -e \"$lock\" ]; then echo \"lock $(stat -c %Y \"$lock\")\"; else echo \"lock none\"; fi\n\
+            pid=$(tmux -L \"$socket\" list-panes -t {runner} -F '#{{pane_pid}}' 2>/dev/null | head -n1)\n\
+            echo \"pid ${{pid:-none}}\"",
+            user = self.user,
+            runner = self.runner,
+        );
+
+        let output = match self.su(&check, STATUS_TIMEOUT).await {
+            Ok(output) if output.success() => output,
+            Ok(output) => {
+                return StatusReport::new(ServerStatus::Unknown(format!(
+                    "status check exited with {:?}",
+                    output.code
+                )));
+            }
+            Err(e) => return StatusReport::new(ServerStatus::Unknown(e.to_string())),
+        };
+
+        let mut lock = None;
+        let mut pid = None;
+        for line in output.stdout.lines() {
+            match line.split_once(' ') {
+                Some(("lock", value)) => lock = Some(value.parse::<u64>().ok()),
+                Some(("pid", value)) => pid = value.parse::<u32>().ok(),
+                _ => {}
+            }
+        }
+
+        match lock {
+            // LGSM removes the lock when stopped, so a lock without a
+            // session means the server died without being told to stop
+            Some(Some(started)) if pid.is_some() => StatusReport::new(ServerStatus::Running)
+                .started_at(started)
+                .with_pid(pid),
+            Some(Some(started)) => StatusReport::new(ServerStatus::Crashed).started_at(started),
+            Some(None) => StatusReport::new(ServerStatus::Stopped),
+            None => StatusReport::new(ServerStatus::Unknown(
+                "unexpected status check output".to_string(),
+            )),
+        }
+    }
+}
+
+fn parse_check_update(stdout: &str) -> UpdateCheck {
+    let stdout = strip_colours(stdout);
+    let build = |label: &str| {
+        stdout.lines().find_map(|line| {
+            let (_, build) = line.split_once(label)?;
+            build.split_whitespace().next().map(str::to_string)
+        })
+    };
+
+    let installed = build("Local build:");
+    let available = build("Remote build:");
+    let pending = match (&installed, &available) {
+        (Some(installed), Some(available)) => installed != available,
+        _ => !stdout.contains("No update available"),
+    };
+
+    UpdateCheck {
+        installed,
+        available,
+        pending,
+    }
+}
+
+/// Remove terminal colour codes like `\x1b[32m`
+fn strip_colours(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip up to and including the final letter of the sequence
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ScriptedRunner;
+
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    fn backend(host: &Arc<ScriptedRunner>) -> LgsmBackend {
+        LgsmBackend::new("gs_7days", "sdtdserver", host.clone())
+    }
+
+    #[tokio::test]
+    async fn start_runs_the_lgsm_script_as_the_server_user() {
+        let host = Arc::new(ScriptedRunner::new().exits(0));
+
+        assert_eq!(
+            backend(&host).start(LIMIT).await.unwrap(),
+            OperationOutcome::Started
+        );
+        assert_eq!(
+            host.calls(),
+            ["su - gs_7days -c /home/gs_7days/sdtdserver start"]
+        );
+    }
+
+    #[tokio::test]
+    async fn exit_code_2_means_already_running() {
+        let host = Arc::new(ScriptedRunner::new().exits(2));
+        assert_eq!(
+            backend(&host).start(LIMIT).await.unwrap(),
+            OperationOutcome::AlreadyRunning
+        );
+    }
+
+    #[tokio::test]
+    async fn exit_code_2_means_already_stopped() {
+        let host = Arc::new(ScriptedRunner::new().exits(2));
+
+        assert_eq!(
+            backend(&host).stop(LIMIT).await.unwrap(),
+            OperationOutcome::AlreadyStopped
+        );
+        assert_eq!(
+            host.calls(),
+            ["su - gs_7days -c /home/gs_7days/sdtdserver stop"]
+        );
+    }
+
+    #[tokio::test]
+    async fn update_reports_when_nothing_changed() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(0, "Checking for update: SteamCMD: No update available\n")
+                .prints(0, "Applying update: SteamCMD\n"),
+        );
+        let backend = backend(&host);
+
+        assert_eq!(
+            backend.update(LIMIT).await.unwrap(),
+            OperationOutcome::UpToDate(None)
+        );
+        assert_eq!(
+            backend.update(LIMIT).await.unwrap(),
+            OperationOutcome::Updated {
+                from: None,
+                to: None
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn check_update_reads_both_builds() {
+        let host = Arc::new(ScriptedRunner::new().prints(
+            0,
+            "[ \x1b[32m OK \x1b[0m ] Checking for update: SteamCMD\n\
+             * Local build: \x1b[31m1234567\x1b[0m\n\
+             * Remote build: \x1b[32m1239999\x1b[0m\n\
+             Update available\n",
+        ));
+
+        assert_eq!(
+            backend(&host).check_update(LIMIT).await.unwrap(),
+            UpdateCheck {
+                installed: Some("1234567".to_string()),
+                available: Some("1239999".to_string()),
+                pending: true,
+            }
+        );
+        assert_eq!(
+            host.calls(),
+            ["su - gs_7days -c /home/gs_7days/sdtdserver check-update"]
+        );
+    }
+
+    #[test]
+    fn check_update_without_builds_trusts_the_message() {
+        assert!(!parse_check_update("Checking for update: SteamCMD: No update available").pending);
+        assert!(parse_check_update("Checking for update: Mojang: Update available").pending);
+    }
+
+    #[tokio::test]
+    async fn other_exit_codes_fail() {
+        let host = Arc::new(ScriptedRunner::new().exits(1).exits(2));
+        let backend = backend(&host);
+
+        assert!(matches!(
+            backend.start(LIMIT).await,
+            Err(GameServerError::NonZeroExit { code: Some(1), .. })
+        ));
+        // exit code 2 only means "already done" for start and stop
+        assert!(matches!(
+            backend.update(LIMIT).await,
+            Err(GameServerError::NonZeroExit { code: Some(2), .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn spawn_failures_do_not_panic() {
+        let host = Arc::new(ScriptedRunner::new().fails_to_spawn());
+        assert!(matches!(
+            backend(&host).restart(LIMIT).await,
+            Err(GameServerError::CommandSpawnFailed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_reported() {
+        let host = Arc::new(ScriptedRunner::new().times_out());
+        assert!(matches!(
+            backend(&host).update(LIMIT).await,
+            Err(GameServerError::Timeout { limit: LIMIT, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn status_reads_the_lock_file_and_session() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(0, "lock 1700000000\npid 4242\n")
+                .prints(0, "lock none\npid none\n")
+                .prints(0, "lock 1700000000\npid none\n")
+                .prints(0, "")
+                .exits(1),
+        );
+        let backend = backend(&host);
+
+        let running = backend.status().await;
+        assert_eq!(running.status, ServerStatus::Running);
+        assert_eq!(running.pid, Some(4242));
+        assert!(running.uptime.is_some());
+
+        assert_eq!(backend.status().await.status, ServerStatus::Stopped);
+        assert_eq!(backend.status().await.status, ServerStatus::Crashed);
+        assert!(matches!(
+            backend.status().await.status,
+            ServerStatus::Unknown(_)
+        ));
+        assert!(matches!(
+            backend.status().await.status,
+            ServerStatus::Unknown(_)
+        ));
+    }
+}
@@ -1,55 +1,161 @@
-mod enshrouded;
-mod hytale;
-mod satisfactory;
-mod sevendays;
-mod skyvaults;
-// mod vaulthunters;
-mod zomboid;
-
-use std::sync::LazyLock;
-
-/// Array of all game servers to add to the manager
-pub static GAME_SERVERS: LazyLock<[Box<dyn GameServer>; 6]> = LazyLock::new(|| {
-    [
-        Box::new(sevendays::Server::new()),
-        Box::new(enshrouded::Server::new()),
-        Box::new(hytale::Server::new()),
-        Box::new(satisfactory::Server::new()),
-        Box::new(skyvaults::Server::new()),
-        // Box::new(vaulthunters::Server::new()),
-        Box::new(zomboid::Server::new()),
-    ]
-});
+mod backup;
+mod error;
+mod game;
+mod lgsm;
+mod runner;
+mod status;
+mod steam;
+mod systemd;
+mod tmux;
+mod versions;
 
+pub use backup::{ArchiveBackups, BackupInfo, Backups, LgsmBackups};
+pub use error::{GameServerError, OperationOutcome, OperationResult};
+pub use game::Game;
+pub use lgsm::LgsmBackend;
+#[cfg(test)]
+pub use runner::ScriptedRunner;
+pub use runner::{CommandOutput, CommandRunner, Deadline, STATUS_TIMEOUT, SystemRunner};
+pub use status::{ServerStatus, StatusReport, format_duration};
+pub use steam::Steamcmd;
+pub use systemd::SystemdBackend;
+pub use tmux::TmuxBackend;
+pub use versions::{UpdateCheck, VersionCommands};
+
+use crate::config::{BackendConfig, Config, ServerConfig};
+
+use serenity::async_trait;
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// All game servers declared in the config, set once at startup
+static GAME_SERVERS: OnceLock<Vec<Box<dyn GameServer>>> = OnceLock::new();
+
+#[async_trait]
 pub trait GameServer: Send + Sync {
-    fn new() -> Self
-    where
-        Self: Sized;
     fn name(&self) -> &str;
     fn description(&self) -> &str;
     fn port(&self) -> u16;
-    fn help_message(&self) -> String;
-    fn start(&self) -> String;
-    fn stop(&self) -> String;
-    fn restart(&self) -> String;
-    fn update(&self) -> String;
-    fn status(&self) -> String;
+    /// How long the server may sit empty before it is stopped
+    fn idle_timeout(&self) -> Option<Duration>;
+    async fn help_message(&self) -> String;
+    async fn start(&self) -> OperationResult;
+    async fn stop(&self) -> OperationResult;
+    async fn restart(&self) -> OperationResult;
+    async fn update(&self) -> OperationResult;
+    /// Why [GameServer::update] would be refused right now, if it would
+    async fn can_update(&self) -> Result<(), GameServerError>;
+    /// Installed and available versions, without updating
+    async fn check_update(&self) -> Result<UpdateCheck, GameServerError>;
+    async fn backup(&self) -> OperationResult;
+    /// Every backup of the server, newest first
+    async fn backups(&self) -> Result<Vec<BackupInfo>, GameServerError>;
+    /// Restore a backup by id, refusing while players are online
+    async fn restore(&self, id: &str) -> OperationResult;
+    async fn status(&self) -> StatusReport;
+    /// The last status if it is younger than `max_age`, otherwise a fresh one
+    async fn recent_status(&self, max_age: Duration) -> StatusReport;
+    /// The last status however old, without ever asking the host
+    fn cached_status(&self) -> Option<StatusReport>;
+    /// What the last start or stop asked for, if anything has since startup
+    fn expected_status(&self) -> Option<ServerStatus>;
+    /// Console commands allowed through `/console`, empty without RCON
+    fn console_commands(&self) -> &[String];
+    /// Run an allowlisted command on the in-game console
+    async fn console(&self, command: &str) -> Result<String, GameServerError>;
+    /// Message every player in-game, where the console supports it
+    async fn broadcast(&self, message: &str) -> Result<(), GameServerError>;
+}
+
+/// Process management for a game server, independent of the game itself
+///
+/// Backends only report what happened, so every game behaves the same way.
+/// Anything still running after `timeout` is killed and reported as
+/// [GameServerError::Timeout].
+#[async_trait]
+pub trait Backend: Send + Sync {
+    async fn start(&self, timeout: Duration) -> OperationResult;
+    async fn stop(&self, timeout: Duration) -> OperationResult;
+    async fn restart(&self, timeout: Duration) -> OperationResult;
+    async fn update(&self, timeout: Duration) -> OperationResult;
+    /// Why an update would be refused right now, checked before players are
+    /// warned about it
+    async fn can_update(&self) -> Result<(), GameServerError> {
+        Ok(())
+    }
+    async fn check_update(&self, _timeout: Duration) -> Result<UpdateCheck, GameServerError> {
+        Err(GameServerError::NotSupported("can not check for updates"))
+    }
+    async fn status(&self) -> StatusReport;
 }
 
-/// Get the server's public IP
+/// Build every enabled game server from the config
 ///
-/// WARNING! do not do this with anyone untrusted in the discord guild
-pub fn public_ip() -> String {
-    if let Ok(response) = minreq::get("https://ident.me").send() {
-        if let Ok(ip) = response.as_str() {
-            return ip.to_string();
-        }
+/// Must be called before the discord client starts, since registering the
+/// slash commands needs the full list of servers
+pub fn init(config: &Config) {
+    let host: Arc<dyn CommandRunner> = Arc::new(SystemRunner);
+    let servers = config
+        .enabled_servers()
+        .map(|server| build_server(server, host.clone()))
+        .collect();
+
+    if GAME_SERVERS.set(servers).is_err() {
+        eprintln!("Game servers were already initialised, ignoring the new config");
+    }
+}
+
+/// Every game server available to the discord guild
+pub fn all() -> &'static [Box<dyn GameServer>] {
+    GAME_SERVERS
+        .get()
+        .expect("game servers must be initialised at startup")
+}
+
+fn build_server(config: &ServerConfig, host: Arc<dyn CommandRunner>) -> Box<dyn GameServer> {
+    let backups: Option<Box<dyn Backups>> = match (&config.backend, &config.backup) {
+        (BackendConfig::Lgsm { runner }, _) => Some(Box::new(LgsmBackups::new(
+            &config.user,
+            runner,
+            host.clone(),
+        ))),
+        (_, Some(backup)) => Some(Box::new(ArchiveBackups::new(
+            &config.name,
+            &config.user,
+            backup.clone(),
+            host.clone(),
+        ))),
+        (_, None) => None,
+    };
+
+    let versions = config
+        .versions
+        .clone()
+        .map(|versions| VersionCommands::new(&config.user, versions, host.clone()));
+
+    let backend: Box<dyn Backend> = match &config.backend {
+        BackendConfig::Lgsm { runner } => Box::new(LgsmBackend::new(&config.user, runner, host)),
+        BackendConfig::Tmux {
+            session,
+            command,
+            steam,
+            shutdown,
+        } => Box::new(TmuxBackend::new(
+            &config.user,
+            session,
+            command,
+            steam.clone(),
+            shutdown.clone(),
+            host,
+        )),
+        BackendConfig::Systemd { unit } => Box::new(SystemdBackend::new(unit, host)),
     };
 
-    "unknown".to_string()
+    Box::new(Game::new(config.clone(), backend, backups, versions))
 }
 
 /// Get the game server data
-pub fn get_game_server(name: &str) -> Option<&dyn GameServer> {
-    GAME_SERVERS.iter().find(|g| g.name() == name).map(|v| &**v)
+pub fn get_game_server(name: &str) -> Option<&'static dyn GameServer> {
+    all().iter().find(|g| g.name() == name).map(|v| &**v)
 }
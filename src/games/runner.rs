@@ -0,0 +1,323 @@
+use crate::games::GameServerError;
+
+use serenity::async_trait;
+
+use std::time::{Duration, Instant};
+
+/// How long quick status checks are allowed to take
+pub const STATUS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whatever a finished host command left behind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Exit code, or `None` if the process was killed by a signal
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Turn a failed exit into a [GameServerError::NonZeroExit]
+    pub fn check(self, command: &str) -> Result<CommandOutput, GameServerError> {
+        if self.success() {
+            Ok(self)
+        } else {
+            Err(GameServerError::non_zero(command, self.code, &self.stderr))
+        }
+    }
+}
+
+/// One timeout shared by every step of an operation, so a few slow steps
+/// can not add up to more than the configured limit
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    at: Instant,
+    limit: Duration,
+}
+
+impl Deadline {
+    pub fn after(limit: Duration) -> Self {
+        Deadline {
+            at: Instant::now() + limit,
+            limit,
+        }
+    }
+
+    /// Time left for the next step
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// Report a step that timed out against the whole operation's limit
+    pub fn overall<T>(&self, result: Result<T, GameServerError>) -> Result<T, GameServerError> {
+        result.map_err(|error| match error {
+            GameServerError::Timeout { command, .. } => GameServerError::Timeout {
+                command,
+                limit: self.limit,
+            },
+            other => other,
+        })
+    }
+}
+
+/// Space separated command line, for logs and error messages
+pub fn command_line(program: &str, args: &[&str]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Runs commands on the host for the backends
+///
+/// Backends never touch `tokio::process` directly so the real host can be
+/// swapped out for a scripted one in tests
+#[async_trait]
+pub trait CommandRunner: Send + Sync {
+    async fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<CommandOutput, GameServerError>;
+}
+
+/// Runs commands for real with `tokio::process::Command`
+pub struct SystemRunner;
+
+#[async_trait]
+impl CommandRunner for SystemRunner {
+    async fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<CommandOutput, GameServerError> {
+        let spawn_failed = |source| GameServerError::CommandSpawnFailed {
+            command: command_line(program, args),
+            source,
+        };
+
+        // in its own process group, as the child is usually sudo or su and
+        // killing only that would leave steamcmd and the like running
+        let child = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(spawn_failed)?;
+        let group = child.id();
+
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => output.map_err(spawn_failed)?,
+            Err(_) => {
+                if let Some(group) = group {
+                    kill_group(group);
+                }
+                return Err(GameServerError::Timeout {
+                    command: command_line(program, args),
+                    limit: timeout,
+                });
+            }
+        };
+
+        Ok(CommandOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// SIGKILL every process left in a command's group, which has the same id as
+/// the command itself
+fn kill_group(group: u32) {
+    let Ok(group) = libc::pid_t::try_from(group) else {
+        return;
+    };
+
+    // SAFETY: killpg only sends a signal, and the group is one we created
+    if unsafe { libc::killpg(group, libc::SIGKILL) } != 0 {
+        let error = std::io::Error::last_os_error();
+        // the group is already gone if everything in it exited
+        if error.raw_os_error() != Some(libc::ESRCH) {
+            eprintln!("Failed to kill process group {group}: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+pub use scripted::ScriptedRunner;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn system_runner_captures_output() {
+        let output = SystemRunner
+            .run("sh", &["-c", "echo hello; exit 3"], STATUS_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(output.code, Some(3));
+        assert_eq!(output.stdout, "hello\n");
+    }
+
+    #[test]
+    fn deadlines_report_the_whole_limit() {
+        let limit = Duration::from_secs(30 * 60);
+        let deadline = Deadline::after(limit);
+        assert!(deadline.remaining() <= limit);
+
+        let step: Result<(), _> = Err(GameServerError::Timeout {
+            command: "steamcmd".to_string(),
+            limit: Duration::from_secs(60),
+        });
+        assert!(matches!(
+            deadline.overall(step),
+            Err(GameServerError::Timeout { limit: l, .. }) if l == limit
+        ));
+    }
+
+    /// Whether a process is still running, rather than gone or a zombie
+    /// waiting to be reaped
+    fn is_running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .is_ok_and(|stat| !stat.rsplit(')').next().unwrap_or("").starts_with(" Z"))
+    }
+
+    #[tokio::test]
+    async fn system_runner_kills_commands_that_time_out() {
+        // a wrapper like sudo, with the real work in a grandchild
+        let pid_file = std::env::temp_dir().join(format!("runner-{}.pid", std::process::id()));
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let limit = Duration::from_millis(500);
+        let started = std::time::Instant::now();
+
+        let result = SystemRunner.run("sh", &["-c", &script], limit).await;
+
+        assert!(matches!(
+            result,
+            Err(GameServerError::Timeout { limit: l, .. }) if l == limit
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let grandchild = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        let gone = Instant::now();
+        while is_running(grandchild.trim()) {
+            assert!(
+                gone.elapsed() < Duration::from_secs(5),
+                "sleep is still running"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod scripted {
+    use super::{CommandOutput, CommandRunner, command_line};
+    use crate::games::GameServerError;
+    use serenity::async_trait;
+    use std::collections::VecDeque;
+    use std::io;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// What a scripted command does when it is run
+    enum Response {
+        Output(CommandOutput),
+        SpawnFailure,
+        Timeout,
+    }
+
+    /// Fake runner that replays canned results and records every invocation
+    #[derive(Default)]
+    pub struct ScriptedRunner {
+        responses: Mutex<VecDeque<Response>>,
+        calls: Mutex<Vec<String>>,
+        timeouts: Mutex<Vec<Duration>>,
+    }
+
+    impl ScriptedRunner {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Queue a command that exits with `code` and prints nothing
+        pub fn exits(self, code: i32) -> Self {
+            self.prints(code, "")
+        }
+
+        /// Queue a command that exits with `code` after printing `stdout`
+        pub fn prints(self, code: i32, stdout: &str) -> Self {
+            self.push(Response::Output(CommandOutput {
+                code: Some(code),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            }))
+        }
+
+        /// Queue a command that could not be spawned at all
+        pub fn fails_to_spawn(self) -> Self {
+            self.push(Response::SpawnFailure)
+        }
+
+        /// Queue a command that runs past its timeout
+        pub fn times_out(self) -> Self {
+            self.push(Response::Timeout)
+        }
+
+        fn push(self, response: Response) -> Self {
+            self.responses.lock().unwrap().push_back(response);
+            self
+        }
+
+        /// Every command run so far, as space separated command lines
+        pub fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// The timeout every command so far was given, in the same order
+        pub fn timeouts(&self) -> Vec<Duration> {
+            self.timeouts.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl CommandRunner for ScriptedRunner {
+        async fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            timeout: Duration,
+        ) -> Result<CommandOutput, GameServerError> {
+            let line = command_line(program, args);
+            self.calls.lock().unwrap().push(line.clone());
+            self.timeouts.lock().unwrap().push(timeout);
+
+            let response = self.responses.lock().unwrap().pop_front();
+            match response {
+                Some(Response::Output(output)) => Ok(output),
+                Some(Response::SpawnFailure) => Err(GameServerError::CommandSpawnFailed {
+                    command: line,
+                    source: io::Error::new(io::ErrorKind::NotFound, "not found"),
+                }),
+                Some(Response::Timeout) => Err(GameServerError::Timeout {
+                    command: line,
+                    limit: timeout,
+                }),
+                None => panic!("unexpected command: {line}"),
+            }
+        }
+    }
+}
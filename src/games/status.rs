@@ -0,0 +1,110 @@
+use crate::query::ServerInfo;
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What a game server is currently doing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerStatus {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    Updating,
+    BackingUp,
+    Restoring,
+    /// The server should be running but the process is gone or failed
+    Crashed,
+    /// The state could not be determined, with the reason why
+    Unknown(String),
+}
+
+impl ServerStatus {
+    /// Whether the server process is up, even if it is still loading
+    pub fn is_up(&self) -> bool {
+        matches!(self, ServerStatus::Starting | ServerStatus::Running)
+    }
+}
+
+impl fmt::Display for ServerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerStatus::Stopped => write!(f, "Stopped"),
+            ServerStatus::Starting => write!(f, "Starting"),
+            ServerStatus::Running => write!(f, "Running"),
+            ServerStatus::Stopping => write!(f, "Stopping"),
+            ServerStatus::Updating => write!(f, "Updating"),
+            ServerStatus::BackingUp => write!(f, "Backing up"),
+            ServerStatus::Restoring => write!(f, "Restoring a backup"),
+            ServerStatus::Crashed => write!(f, "Crashed"),
+            ServerStatus::Unknown(reason) => write!(f, "Unknown ({reason})"),
+        }
+    }
+}
+
+/// Everything known about a server's state at the time it was checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusReport {
+    pub status: ServerStatus,
+    /// Process id of the server, where the backend can find it
+    pub pid: Option<u32>,
+    /// How long the server process has been up
+    pub uptime: Option<Duration>,
+    /// When the server last changed state
+    pub since: Option<SystemTime>,
+    /// Live details from the game's query protocol, if it answered
+    pub info: Option<ServerInfo>,
+}
+
+impl StatusReport {
+    /// A report with nothing but the status itself
+    pub fn new(status: ServerStatus) -> Self {
+        StatusReport {
+            status,
+            pid: None,
+            uptime: None,
+            since: None,
+            info: None,
+        }
+    }
+
+    /// Fill in `since` and `uptime` from a unix timestamp of when it started
+    pub fn started_at(mut self, epoch_secs: u64) -> Self {
+        let started = UNIX_EPOCH + Duration::from_secs(epoch_secs);
+        self.since = Some(started);
+        self.uptime = SystemTime::now().duration_since(started).ok();
+        self
+    }
+
+    pub fn with_pid(mut self, pid: Option<u32>) -> Self {
+        self.pid = pid;
+        self
+    }
+
+    /// One line summary for discord, e.g. "Running — 3/8 players (up 2h 5m)"
+    pub fn summary(&self) -> String {
+        let mut summary = self.status.to_string();
+
+        if let Some(info) = &self.info {
+            summary.push_str(&format!(" — {}", info.player_summary()));
+        }
+
+        if let Some(uptime) = self.uptime.filter(|_| self.status.is_up()) {
+            summary.push_str(&format!(" (up {})", format_duration(uptime)));
+        }
+
+        summary
+    }
+}
+
+/// Human readable duration to the nearest minute, e.g. "1d 2h 5m"
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, (minutes / 60) % 24, minutes % 60);
+
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
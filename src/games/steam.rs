@@ -0,0 +1,293 @@
+//! Updates through steamcmd, comparing the installed build with the latest
+//! one first so an unchanged server is not downloaded again
+use crate::config::SteamConfig;
+use crate::games::{
+    CommandOutput, CommandRunner, Deadline, GameServerError, OperationOutcome, OperationResult,
+    UpdateCheck,
+};
+
+use std::time::Duration;
+
+/// A steam app installed in the server user's `serverfiles`
+pub struct Steamcmd<'a> {
+    user: &'a str,
+    steam: &'a SteamConfig,
+    host: &'a dyn CommandRunner,
+}
+
+impl<'a> Steamcmd<'a> {
+    pub fn new(user: &'a str, steam: &'a SteamConfig, host: &'a dyn CommandRunner) -> Self {
+        Steamcmd { user, steam, host }
+    }
+
+    fn install_dir(&self) -> String {
+        format!("/home/{}/serverfiles", self.user)
+    }
+
+    /// Run steamcmd as the server user, logged in anonymously
+    async fn steamcmd(
+        &self,
+        commands: &[&str],
+        timeout: Duration,
+    ) -> Result<CommandOutput, GameServerError> {
+        let install_dir = self.install_dir();
+        let mut args = vec!["-u", self.user, "/usr/games/steamcmd"];
+
+        if let Some(platform) = &self.steam.platform {
+            args.extend(["+@sSteamCmdForcePlatformType", platform]);
+        }
+
+        args.extend(["+force_install_dir", &install_dir, "+login", "anonymous"]);
+        args.extend_from_slice(commands);
+        args.push("+quit");
+
+        self.host.run("sudo", &args, timeout).await
+    }
+
+    /// Build id from the app manifest, or `None` if it is not installed
+    pub async fn installed_build(&self, timeout: Duration) -> Option<u64> {
+        let manifest = format!(
+            "{}/steamapps/appmanifest_{}.acf",
+            self.install_dir(),
+            self.steam.app_id
+        );
+
+        match self
+            .host
+            .run("sudo", &["-u", self.user, "cat", &manifest], timeout)
+            .await
+        {
+            Ok(output) if output.success() => manifest_build(&output.stdout),
+            _ => None,
+        }
+    }
+
+    /// Build id of the public branch on steam
+    pub async fn latest_build(&self, timeout: Duration) -> Result<u64, GameServerError> {
+        let app_id = self.steam.app_id.to_string();
+        let commands = ["+app_info_update", "1", "+app_info_print", &app_id];
+        let output = self.steamcmd(&commands, timeout).await?;
+
+        public_build(&output.stdout).ok_or_else(|| {
+            GameServerError::non_zero(
+                format!("steamcmd +app_info_print {app_id}"),
+                output.code,
+                "no public build id in the output",
+            )
+        })
+    }
+
+    /// Compare the installed build with the latest one, without downloading
+    pub async fn check(&self, timeout: Duration) -> Result<UpdateCheck, GameServerError> {
+        let deadline = Deadline::after(timeout);
+        let installed = self.installed_build(deadline.remaining()).await;
+        let latest = deadline.overall(self.latest_build(deadline.remaining()).await)?;
+        Ok(UpdateCheck::compare(
+            installed.map(|build| build.to_string()),
+            latest.to_string(),
+        ))
+    }
+
+    /// Download the latest build, unless it is already installed
+    pub async fn update(&self, timeout: Duration) -> OperationResult {
+        let deadline = Deadline::after(timeout);
+        deadline.overall(self.update_before(deadline).await)
+    }
+
+    async fn update_before(&self, deadline: Deadline) -> OperationResult {
+        let installed = self.installed_build(deadline.remaining()).await;
+
+        // without the latest build there is nothing to compare, so update
+        // anyway rather than leave the server out of date
+        let latest = match self.latest_build(deadline.remaining()).await {
+            Ok(latest) => Some(latest),
+            Err(e) => {
+                eprintln!(
+                    "Could not check the latest build, updating anyway: {}",
+                    e.details()
+                );
+                None
+            }
+        };
+
+        if let (Some(installed), Some(latest)) = (installed, latest) {
+            if installed == latest {
+                return Ok(OperationOutcome::UpToDate(Some(latest)));
+            }
+        }
+
+        let app_id = self.steam.app_id.to_string();
+        let output = self
+            .steamcmd(&["+app_update", &app_id], deadline.remaining())
+            .await?;
+
+        // steamcmd happily exits with 0 after a failed update, so only trust
+        // its own success message
+        if output.stdout.contains("Success!") {
+            return Ok(OperationOutcome::Updated {
+                from: installed,
+                to: latest,
+            });
+        }
+
+        let error = output
+            .stdout
+            .lines()
+            .rfind(|line| line.contains("Error"))
+            .unwrap_or(&output.stderr);
+
+        Err(GameServerError::non_zero(
+            format!("steamcmd +app_update {app_id}"),
+            output.code,
+            error,
+        ))
+    }
+}
+
+/// The `buildid` from an `appmanifest_<app id>.acf`
+fn manifest_build(manifest: &str) -> Option<u64> {
+    manifest.lines().find_map(|line| vdf_value(line, "buildid"))
+}
+
+/// The public branch's `buildid` from `+app_info_print`, skipping the
+/// depots, which have their own `public` entries
+fn public_build(app_info: &str) -> Option<u64> {
+    let mut lines = app_info
+        .lines()
+        .skip_while(|line| line.trim() != "\"branches\"");
+    lines
+        .find(|line| line.trim() == "\"public\"")
+        .and_then(|_| lines.find_map(|line| vdf_value(line, "buildid")))
+}
+
+/// Parse a `"key"  "value"` line as a number, if it is for `key`
+fn vdf_value(line: &str, key: &str) -> Option<u64> {
+    let mut fields = line.split('"').filter(|field| !field.trim().is_empty());
+    if fields.next()? != key {
+        return None;
+    }
+    fields.next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ScriptedRunner;
+
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    const APP_INFO: &str = r#"
+"2278520"
+{
+	"common"
+	{
+		"name"		"Enshrouded Dedicated Server"
+	}
+	"depots"
+	{
+		"2278521"
+		{
+			"manifests"
+			{
+				"public"
+				{
+					"gid"		"5071245612736180743"
+					"size"		"2341958376"
+				}
+			}
+		}
+		"branches"
+		{
+			"public"
+			{
+				"buildid"		"16410553"
+				"timeupdated"		"1730196842"
+			}
+			"experimental"
+			{
+				"buildid"		"16500001"
+			}
+		}
+	}
+}
+"#;
+
+    const MANIFEST: &str = r#"
+"AppState"
+{
+	"appid"		"2278520"
+	"name"		"Enshrouded Dedicated Server"
+	"StateFlags"		"4"
+	"buildid"		"16310290"
+	"LastOwner"		"0"
+}
+"#;
+
+    fn steam() -> SteamConfig {
+        SteamConfig {
+            app_id: 2278520,
+            platform: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn an_unchanged_build_is_not_downloaded() {
+        let installed = MANIFEST.replace("16310290", "16410553");
+        let host = ScriptedRunner::new()
+            .prints(0, &installed)
+            .prints(0, APP_INFO);
+
+        assert_eq!(
+            Steamcmd::new("gs_enshrouded", &steam(), &host)
+                .update(LIMIT)
+                .await
+                .unwrap(),
+            OperationOutcome::UpToDate(Some(16410553))
+        );
+        assert_eq!(
+            host.calls(),
+            [
+                "sudo -u gs_enshrouded cat \
+                 /home/gs_enshrouded/serverfiles/steamapps/appmanifest_2278520.acf",
+                "sudo -u gs_enshrouded /usr/games/steamcmd \
+                 +force_install_dir /home/gs_enshrouded/serverfiles +login anonymous \
+                 +app_info_update 1 +app_info_print 2278520 +quit",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_new_build_is_downloaded() {
+        let host = ScriptedRunner::new()
+            .prints(0, MANIFEST)
+            .prints(0, APP_INFO)
+            .prints(0, "Success! App '2278520' fully installed.");
+
+        assert_eq!(
+            Steamcmd::new("gs_enshrouded", &steam(), &host)
+                .update(LIMIT)
+                .await
+                .unwrap(),
+            OperationOutcome::Updated {
+                from: Some(16310290),
+                to: Some(16410553)
+            }
+        );
+        assert!(host.calls()[2].ends_with("+app_update 2278520 +quit"));
+    }
+
+    #[test]
+    fn reads_the_public_build_not_a_depot() {
+        assert_eq!(public_build(APP_INFO), Some(16410553));
+        assert_eq!(
+            public_build("Connecting anonymously to Steam Public...OK"),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_the_installed_build() {
+        assert_eq!(manifest_build(MANIFEST), Some(16310290));
+        assert_eq!(manifest_build(""), None);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::games::{
+    Backend, CommandRunner, GameServerError, OperationOutcome, OperationResult, STATUS_TIMEOUT,
+    ServerStatus, StatusReport,
+};
+
+use serenity::async_trait;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Backend for servers managed as a systemd unit
+pub struct SystemdBackend {
+    /// Systemd unit that runs the game, e.g. `hytale.service`
+    unit: String,
+    /// Runs the commands on the host
+    host: Arc<dyn CommandRunner>,
+}
+
+impl SystemdBackend {
+    pub fn new(unit: &str, host: Arc<dyn CommandRunner>) -> Self {
+        SystemdBackend {
+            unit: unit.to_string(),
+            host,
+        }
+    }
+
+    async fn systemctl(
+        &self,
+        verb: &str,
+        timeout: Duration,
+        done: OperationOutcome,
+        already: OperationOutcome,
+    ) -> OperationResult {
+        let output = self
+            .host
+            .run("systemctl", &[verb, &self.unit], timeout)
+            .await?;
+
+        match output.code {
+            Some(0) => Ok(done),
+            // systemctl returns 0 even if already active, but keep this for safety
+            Some(2) => Ok(already),
+            code => Err(GameServerError::non_zero(
+                format!("systemctl {verb} {}", self.unit),
+                code,
+                &output.stderr,
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for SystemdBackend {
+    async fn start(&self, timeout: Duration) -> OperationResult {
+        use OperationOutcome::{AlreadyRunning, Started};
+        self.systemctl("start", timeout, Started, AlreadyRunning)
+            .await
+    }
+
+    async fn stop(&self, timeout: Duration) -> OperationResult {
+        use OperationOutcome::{AlreadyStopped, Stopped};
+        self.systemctl("stop", timeout, Stopped, AlreadyStopped)
+            .await
+    }
+
+    async fn restart(&self, timeout: Duration) -> OperationResult {
+        use OperationOutcome::Restarted;
+        self.systemctl("restart", timeout, Restarted, Restarted)
+            .await
+    }
+
+    async fn update(&self, _timeout: Duration) -> OperationResult {
+        Err(GameServerError::NotSupported("must be updated manually"))
+    }
+
+    async fn can_update(&self) -> Result<(), GameServerError> {
+        Err(GameServerError::NotSupported("must be updated manually"))
+    }
+
+    async fn status(&self) -> StatusReport {
+        let status = match self
+            .host
+            .run("systemctl", &["is-active", &self.unit], STATUS_TIMEOUT)
+            .await
+        {
+            Ok(output) => match (output.stdout.trim(), output.code) {
+                ("active" | "reloading", _) => ServerStatus::Running,
+                ("activating", _) => ServerStatus::Starting,
+                ("deactivating", _) => ServerStatus::Stopping,
+                ("failed", _) => ServerStatus::Crashed,
+                ("inactive", _) => ServerStatus::Stopped,
+                // older systemd versions may print nothing at all
+                (_, Some(0)) => ServerStatus::Running,
+                (_, Some(1 | 3)) => ServerStatus::Stopped,
+                (_, code) => {
+                    ServerStatus::Unknown(format!("systemctl is-active exited with {code:?}"))
+                }
+            },
+            Err(e) => ServerStatus::Unknown(e.to_string()),
+        };
+
+        if !status.is_up() {
+            return StatusReport::new(status);
+        }
+
+        // details are nice to have, so a failure here is not worth reporting
+        let details = self
+            .host
+            .run(
+                "systemctl",
+                &[
+                    "show",
+                    &self.unit,
+                    "--property=MainPID,ActiveEnterTimestamp",
+                    "--timestamp=unix",
+                ],
+                STATUS_TIMEOUT,
+            )
+            .await;
+
+        let mut report = StatusReport::new(status);
+        if let Ok(output) = details {
+            for line in output.stdout.lines() {
+                match line.split_once('=') {
+                    Some(("MainPID", pid)) => {
+                        report = report.with_pid(pid.parse().ok().filter(|pid| *pid != 0));
+                    }
+                    Some(("ActiveEnterTimestamp", stamp)) => {
+                        if let Ok(started) = stamp.trim_start_matches('@').parse() {
+                            report = report.started_at(started);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ScriptedRunner;
+
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    #[tokio::test]
+    async fn is_active_exit_codes_map_to_status() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                // is-active, then the show that follows a running unit
+                .exits(0)
+                .prints(0, "MainPID=1234\n")
+                .exits(1)
+                .exits(3)
+                .exits(4)
+                .fails_to_spawn(),
+        );
+        let backend = SystemdBackend::new("hytale.service", host.clone());
+
+        assert_eq!(backend.status().await.status, ServerStatus::Running);
+        assert_eq!(backend.status().await.status, ServerStatus::Stopped);
+        assert_eq!(backend.status().await.status, ServerStatus::Stopped);
+        assert!(matches!(
+            backend.status().await.status,
+            ServerStatus::Unknown(_)
+        ));
+        assert!(matches!(
+            backend.status().await.status,
+            ServerStatus::Unknown(_)
+        ));
+        assert_eq!(host.calls()[0], "systemctl is-active hytale.service");
+        assert!(host.calls()[1].starts_with("systemctl show hytale.service"));
+        assert!(
+            host.calls()[2..]
+                .iter()
+                .all(|call| call == "systemctl is-active hytale.service")
+        );
+    }
+
+    #[tokio::test]
+    async fn is_active_states_map_to_status() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(3, "failed\n")
+                .prints(0, "activating\n")
+                .prints(0, "MainPID=0\n")
+                .prints(3, "inactive\n"),
+        );
+        let backend = SystemdBackend::new("hytale.service", host.clone());
+
+        assert_eq!(backend.status().await.status, ServerStatus::Crashed);
+        assert_eq!(backend.status().await.status, ServerStatus::Starting);
+        assert_eq!(backend.status().await.status, ServerStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn running_units_report_pid_and_uptime() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(0, "active\n")
+                .prints(0, "MainPID=4321\nActiveEnterTimestamp=@1700000000\n"),
+        );
+        let backend = SystemdBackend::new("hytale.service", host.clone());
+
+        let report = backend.status().await;
+        assert_eq!(report.status, ServerStatus::Running);
+        assert_eq!(report.pid, Some(4321));
+        assert!(report.uptime.is_some());
+        assert_eq!(
+            host.calls()[1],
+            "systemctl show hytale.service --property=MainPID,ActiveEnterTimestamp \
+             --timestamp=unix"
+        );
+    }
+
+    #[tokio::test]
+    async fn start_and_stop_use_systemctl() {
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(5));
+        let backend = SystemdBackend::new("hytale.service", host.clone());
+
+        assert_eq!(
+            backend.start(LIMIT).await.unwrap(),
+            OperationOutcome::Started
+        );
+        assert!(matches!(
+            backend.stop(LIMIT).await,
+            Err(GameServerError::NonZeroExit { code: Some(5), .. })
+        ));
+        assert_eq!(
+            host.calls(),
+            [
+                "systemctl start hytale.service",
+                "systemctl stop hytale.service"
+            ]
+        );
+    }
+}
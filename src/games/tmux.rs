@@ -0,0 +1,530 @@
+use crate::config::{ShutdownConfig, SteamConfig};
+use crate::games::{
+    Backend, CommandOutput, CommandRunner, Deadline, GameServerError, OperationOutcome,
+    OperationResult, STATUS_TIMEOUT, ServerStatus, StatusReport, Steamcmd, UpdateCheck,
+};
+
+use serenity::async_trait;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often a shutting down session is checked for having exited
+const SHUTDOWN_POLL: Duration = Duration::from_secs(2);
+
+/// Kept back from an operation's timeout for killing a session that did not
+/// exit, however long the shutdown timeout is
+const KILL_RESERVE: Duration = STATUS_TIMEOUT;
+
+/// Backend for servers running in a detached tmux session
+pub struct TmuxBackend {
+    /// User that runs the game server
+    user: String,
+    /// tmux session name
+    session: String,
+    /// Server executable (and arguments) run inside the session
+    command: String,
+    /// Steam app to update, if any
+    steam: Option<SteamConfig>,
+    /// How the server is asked to exit before the session is killed
+    shutdown: ShutdownConfig,
+    /// Runs the commands on the host
+    host: Arc<dyn CommandRunner>,
+}
+
+impl TmuxBackend {
+    pub fn new(
+        user: &str,
+        session: &str,
+        command: &str,
+        steam: Option<SteamConfig>,
+        shutdown: ShutdownConfig,
+        host: Arc<dyn CommandRunner>,
+    ) -> Self {
+        TmuxBackend {
+            user: user.to_string(),
+            session: session.to_string(),
+            command: command.to_string(),
+            steam,
+            shutdown,
+            host,
+        }
+    }
+
+    /// Run a tmux command as the server user
+    async fn tmux(
+        &self,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<CommandOutput, GameServerError> {
+        let mut sudo = vec!["-u", &self.user, "tmux"];
+        sudo.extend_from_slice(args);
+        self.host.run("sudo", &sudo, timeout).await
+    }
+
+    /// Whether the session is there, or an error if tmux could not say
+    async fn session_exists(&self, timeout: Duration) -> Result<bool, GameServerError> {
+        self.tmux(&["has-session", "-t", &self.session], timeout)
+            .await
+            .map(|output| output.success())
+    }
+
+    /// Run a tmux command that changes the session, failing on a non-zero exit
+    async fn change_session(
+        &self,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<(), GameServerError> {
+        self.tmux(args, timeout)
+            .await?
+            .check(&format!("tmux {}", args[0]))?;
+        Ok(())
+    }
+
+    async fn new_session(&self, timeout: Duration) -> Result<(), GameServerError> {
+        let args = ["new-session", "-d", "-s", &self.session, &self.command];
+        self.change_session(&args, timeout).await
+    }
+
+    async fn kill_session(&self, timeout: Duration) -> Result<(), GameServerError> {
+        let args = ["kill-session", "-t", &self.session];
+        self.change_session(&args, timeout).await
+    }
+
+    /// The steam app to update, as nothing else can be updated by the bot
+    fn steam(&self) -> Result<&SteamConfig, GameServerError> {
+        self.steam
+            .as_ref()
+            .ok_or(GameServerError::NotSupported("must be updated manually"))
+    }
+
+    /// Ask the server to exit, killing the session only if it is still there
+    /// after the shutdown timeout. Returns whether it had to be killed.
+    ///
+    /// Killing the session hangs up on the server, which can corrupt saves
+    /// (especially under wine), so it is only ever the last resort. Both the
+    /// wait and the kill come out of the operation's `deadline`, with
+    /// [KILL_RESERVE] of it left for the kill.
+    async fn shut_down(&self, deadline: Deadline) -> Result<bool, GameServerError> {
+        let mut args = vec!["send-keys", "-t", &self.session];
+        match &self.shutdown.input {
+            Some(input) => args.extend([input.as_str(), "Enter"]),
+            // the terminal turns this into a SIGINT for the server
+            None => args.push("C-c"),
+        }
+
+        let before_kill = || deadline.remaining().saturating_sub(KILL_RESERVE);
+        let step = || STATUS_TIMEOUT.min(before_kill());
+        if let Err(e) = self.change_session(&args, step()).await {
+            eprintln!(
+                "Failed to ask {} to shut down: {}",
+                self.session,
+                e.details()
+            );
+        }
+
+        let wait = Duration::from_secs(self.shutdown.timeout).min(before_kill());
+        let asked = Instant::now();
+        loop {
+            // a check that fails says nothing, so the server may still be up
+            match self.session_exists(step()).await {
+                Ok(false) => return Ok(false),
+                Ok(true) => {}
+                Err(e) => eprintln!("Failed to check on {}: {}", self.session, e.details()),
+            }
+            if asked.elapsed() >= wait {
+                break;
+            }
+            tokio::time::sleep(SHUTDOWN_POLL).await;
+        }
+
+        eprintln!(
+            "{} did not exit within {}s, killing the session",
+            self.session,
+            wait.as_secs()
+        );
+        self.kill_session(deadline.remaining()).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Backend for TmuxBackend {
+    async fn start(&self, timeout: Duration) -> OperationResult {
+        if self.session_exists(STATUS_TIMEOUT).await? {
+            return Ok(OperationOutcome::AlreadyRunning);
+        }
+
+        self.new_session(timeout).await?;
+        Ok(OperationOutcome::Started)
+    }
+
+    async fn stop(&self, timeout: Duration) -> OperationResult {
+        if !self.session_exists(STATUS_TIMEOUT).await? {
+            return Ok(OperationOutcome::AlreadyStopped);
+        }
+
+        let deadline = Deadline::after(timeout);
+        if deadline.overall(self.shut_down(deadline).await)? {
+            Ok(OperationOutcome::ForceStopped)
+        } else {
+            Ok(OperationOutcome::Stopped)
+        }
+    }
+
+    async fn restart(&self, timeout: Duration) -> OperationResult {
+        // shut down the session if it exists, then start as usual, all within
+        // the one timeout
+        let deadline = Deadline::after(timeout);
+        let running = deadline.overall(
+            self.session_exists(STATUS_TIMEOUT.min(deadline.remaining()))
+                .await,
+        )?;
+        let forced = running && deadline.overall(self.shut_down(deadline).await)?;
+
+        deadline.overall(self.new_session(deadline.remaining()).await)?;
+        if forced {
+            Ok(OperationOutcome::ForceRestarted)
+        } else {
+            Ok(OperationOutcome::Restarted)
+        }
+    }
+
+    async fn update(&self, timeout: Duration) -> OperationResult {
+        let steam = self.steam()?;
+
+        // steamcmd would rewrite the files the server has open
+        if self.session_exists(STATUS_TIMEOUT).await? {
+            return Err(GameServerError::ServerRunning);
+        }
+
+        Steamcmd::new(&self.user, steam, &*self.host)
+            .update(timeout)
+            .await
+    }
+
+    async fn can_update(&self) -> Result<(), GameServerError> {
+        self.steam()?;
+        if self.session_exists(STATUS_TIMEOUT).await? {
+            return Err(GameServerError::ServerRunning);
+        }
+        Ok(())
+    }
+
+    async fn check_update(&self, timeout: Duration) -> Result<UpdateCheck, GameServerError> {
+        let Some(steam) = &self.steam else {
+            return Err(GameServerError::NotSupported("can not check for updates"));
+        };
+
+        Steamcmd::new(&self.user, steam, &*self.host)
+            .check(timeout)
+            .await
+    }
+
+    async fn status(&self) -> StatusReport {
+        let format = "#{pane_pid} #{session_created}";
+        let args = ["display-message", "-p", "-t", &self.session, format];
+
+        match self.tmux(&args, STATUS_TIMEOUT).await {
+            Ok(output) if output.success() => {
+                let mut fields = output.stdout.split_whitespace();
+                let pid = fields.next().and_then(|pid| pid.parse().ok());
+                let report = StatusReport::new(ServerStatus::Running).with_pid(pid);
+
+                match fields.next().and_then(|created| created.parse().ok()) {
+                    Some(created) => report.started_at(created),
+                    None => report,
+                }
+            }
+            // tmux exits with 1 when there is no such session
+            Ok(_) => StatusReport::new(ServerStatus::Stopped),
+            Err(e) => StatusReport::new(ServerStatus::Unknown(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ScriptedRunner;
+
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    fn backend(host: &Arc<ScriptedRunner>) -> TmuxBackend {
+        TmuxBackend::new(
+            "gs_enshrouded",
+            "enshrouded_server",
+            "/usr/bin/wine enshrouded_server.exe",
+            Some(SteamConfig {
+                app_id: 2278520,
+                platform: Some("windows".into()),
+            }),
+            // never wait, so a server still up after one check is killed
+            ShutdownConfig {
+                input: Some("quit".into()),
+                timeout: 0,
+            },
+            host.clone(),
+        )
+    }
+
+    #[tokio::test]
+    async fn start_skips_an_existing_session() {
+        let host = Arc::new(ScriptedRunner::new().exits(0));
+
+        assert_eq!(
+            backend(&host).start(LIMIT).await.unwrap(),
+            OperationOutcome::AlreadyRunning
+        );
+        assert_eq!(
+            host.calls(),
+            ["sudo -u gs_enshrouded tmux has-session -t enshrouded_server"]
+        );
+    }
+
+    #[tokio::test]
+    async fn start_creates_a_missing_session() {
+        let host = Arc::new(ScriptedRunner::new().exits(1).exits(0));
+
+        assert_eq!(
+            backend(&host).start(LIMIT).await.unwrap(),
+            OperationOutcome::Started
+        );
+        assert_eq!(
+            host.calls()[1],
+            "sudo -u gs_enshrouded tmux new-session -d -s enshrouded_server \
+             /usr/bin/wine enshrouded_server.exe"
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_without_a_session_is_already_stopped() {
+        let host = Arc::new(ScriptedRunner::new().exits(1));
+
+        assert_eq!(
+            backend(&host).stop(LIMIT).await.unwrap(),
+            OperationOutcome::AlreadyStopped
+        );
+        assert_eq!(host.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stop_asks_the_server_to_quit() {
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(0).exits(1));
+
+        assert_eq!(
+            backend(&host).stop(LIMIT).await.unwrap(),
+            OperationOutcome::Stopped
+        );
+        assert_eq!(
+            host.calls()[1],
+            "sudo -u gs_enshrouded tmux send-keys -t enshrouded_server quit Enter"
+        );
+        assert_eq!(host.calls().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn stop_kills_a_server_that_does_not_quit() {
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(0).exits(0).exits(0));
+
+        assert_eq!(
+            backend(&host).stop(LIMIT).await.unwrap(),
+            OperationOutcome::ForceStopped
+        );
+        assert_eq!(
+            host.calls()[3],
+            "sudo -u gs_enshrouded tmux kill-session -t enshrouded_server"
+        );
+    }
+
+    #[tokio::test]
+    async fn the_kill_gets_time_after_a_long_shutdown() {
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(0).exits(0).exits(0));
+        let backend = TmuxBackend::new(
+            "gs_enshrouded",
+            "enshrouded_server",
+            "/usr/bin/wine enshrouded_server.exe",
+            None,
+            // as long as the whole stop may take
+            ShutdownConfig {
+                input: Some("quit".into()),
+                timeout: KILL_RESERVE.as_secs(),
+            },
+            host.clone(),
+        );
+
+        assert_eq!(
+            backend.stop(KILL_RESERVE).await.unwrap(),
+            OperationOutcome::ForceStopped
+        );
+        assert!(host.calls()[3].contains("kill-session"));
+        assert!(host.timeouts()[3] > KILL_RESERVE - Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn a_failed_check_is_not_a_clean_shutdown() {
+        // running, asked to quit, then tmux does not answer in time
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(0).times_out().exits(0));
+
+        assert_eq!(
+            backend(&host).stop(LIMIT).await.unwrap(),
+            OperationOutcome::ForceStopped
+        );
+        assert!(host.calls()[3].contains("kill-session"));
+    }
+
+    #[tokio::test]
+    async fn stop_without_input_interrupts_the_server() {
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(0).exits(1));
+        let backend = TmuxBackend::new(
+            "gs_valheim",
+            "valheim",
+            "./valheim_server.x86_64",
+            None,
+            ShutdownConfig::default(),
+            host.clone(),
+        );
+
+        assert_eq!(
+            backend.stop(LIMIT).await.unwrap(),
+            OperationOutcome::Stopped
+        );
+        assert_eq!(
+            host.calls()[1],
+            "sudo -u gs_valheim tmux send-keys -t valheim C-c"
+        );
+    }
+
+    #[tokio::test]
+    async fn restart_reports_a_forced_shutdown() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .exits(0)
+                .exits(0)
+                .exits(0)
+                .exits(0)
+                .exits(0),
+        );
+
+        assert_eq!(
+            backend(&host).restart(LIMIT).await.unwrap(),
+            OperationOutcome::ForceRestarted
+        );
+        assert!(host.calls()[4].contains("new-session"));
+    }
+
+    #[tokio::test]
+    async fn restart_timeouts_are_reported() {
+        // running, asked to quit, still there, then the kill runs too long
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(0).exits(0).times_out());
+        assert!(matches!(
+            backend(&host).restart(LIMIT).await,
+            Err(GameServerError::Timeout { limit: LIMIT, .. })
+        ));
+        assert!(host.calls()[3].contains("kill-session"));
+    }
+
+    #[tokio::test]
+    async fn status_follows_the_session() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(0, "1234 1700000000\n")
+                .exits(1)
+                .fails_to_spawn(),
+        );
+        let backend = backend(&host);
+
+        let running = backend.status().await;
+        assert_eq!(running.status, ServerStatus::Running);
+        assert_eq!(running.pid, Some(1234));
+        assert!(running.since.is_some());
+
+        assert_eq!(backend.status().await.status, ServerStatus::Stopped);
+        assert!(matches!(
+            backend.status().await.status,
+            ServerStatus::Unknown(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_timeouts_are_reported() {
+        // not running, no manifest, then the build check and the update both
+        // run too long
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .exits(1)
+                .exits(1)
+                .times_out()
+                .times_out(),
+        );
+        assert!(matches!(
+            backend(&host).update(LIMIT).await,
+            Err(GameServerError::Timeout { limit: LIMIT, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_needs_steamcmd_success() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .exits(1)
+                .exits(1)
+                .exits(0)
+                .prints(0, "Success! App '2278520' fully installed.")
+                .exits(1)
+                .exits(1)
+                .exits(0)
+                .prints(0, "Error! App '2278520' state is 0x202"),
+        );
+        let backend = backend(&host);
+
+        assert_eq!(
+            backend.update(LIMIT).await.unwrap(),
+            OperationOutcome::Updated {
+                from: None,
+                to: None
+            }
+        );
+        assert!(matches!(
+            backend.update(LIMIT).await,
+            Err(GameServerError::NonZeroExit { stderr, .. }) if stderr.contains("0x202")
+        ));
+        assert!(host.calls()[3].contains("+@sSteamCmdForcePlatformType windows"));
+    }
+
+    #[tokio::test]
+    async fn can_update_only_a_stopped_steam_server() {
+        let host = Arc::new(ScriptedRunner::new().exits(0).exits(1));
+        let backend = backend(&host);
+
+        assert!(matches!(
+            backend.can_update().await,
+            Err(GameServerError::ServerRunning)
+        ));
+        assert!(backend.can_update().await.is_ok());
+
+        let manual = TmuxBackend::new(
+            "gs_valheim",
+            "valheim",
+            "./valheim_server.x86_64",
+            None,
+            ShutdownConfig::default(),
+            host.clone(),
+        );
+        assert!(matches!(
+            manual.can_update().await,
+            Err(GameServerError::NotSupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_refuses_a_running_server() {
+        let host = Arc::new(ScriptedRunner::new().exits(0));
+
+        assert!(matches!(
+            backend(&host).update(LIMIT).await,
+            Err(GameServerError::ServerRunning)
+        ));
+        assert_eq!(host.calls().len(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+//! Whether a server has an update waiting, without installing it
+use crate::config::VersionsConfig;
+use crate::games::{CommandRunner, Deadline, GameServerError};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Installed and available versions, as far as the backend can tell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateCheck {
+    pub installed: Option<String>,
+    pub available: Option<String>,
+    /// Whether running `/update` would change anything
+    pub pending: bool,
+}
+
+impl UpdateCheck {
+    /// Compare two known versions, treating an unknown install as outdated
+    pub fn compare(installed: Option<String>, available: String) -> Self {
+        UpdateCheck {
+            pending: installed.as_ref() != Some(&available),
+            installed,
+            available: Some(available),
+        }
+    }
+}
+
+/// Shell commands from the config that print each version, for servers
+/// whose backend has no way of checking, e.g. Hytale's downloader
+pub struct VersionCommands {
+    user: String,
+    config: VersionsConfig,
+    host: Arc<dyn CommandRunner>,
+}
+
+impl VersionCommands {
+    pub fn new(user: &str, config: VersionsConfig, host: Arc<dyn CommandRunner>) -> Self {
+        VersionCommands {
+            user: user.to_string(),
+            config,
+            host,
+        }
+    }
+
+    pub async fn check(&self, timeout: Duration) -> Result<UpdateCheck, GameServerError> {
+        // a missing install is still worth reporting as outdated
+        let deadline = Deadline::after(timeout);
+        let installed = self
+            .version(&self.config.installed, deadline.remaining())
+            .await
+            .ok();
+        let available = deadline.overall(
+            self.version(&self.config.available, deadline.remaining())
+                .await,
+        )?;
+        Ok(UpdateCheck::compare(installed, available))
+    }
+
+    /// The last line the command prints, as the server user
+    async fn version(&self, command: &str, timeout: Duration) -> Result<String, GameServerError> {
+        let output = self
+            .host
+            .run("su", &["-", &self.user, "-c", command], timeout)
+            .await?
+            .check(command)?;
+
+        output
+            .stdout
+            .lines()
+            .map(str::trim)
+            .rfind(|line| !line.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| GameServerError::non_zero(command, Some(0), "printed no version"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ScriptedRunner;
+
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    fn hytale(host: &Arc<ScriptedRunner>) -> VersionCommands {
+        VersionCommands::new(
+            "gs_hytale",
+            VersionsConfig {
+                installed: "cat server/version.txt".to_string(),
+                available: "./hytale-downloader -print-version".to_string(),
+            },
+            host.clone(),
+        )
+    }
+
+    #[tokio::test]
+    async fn different_versions_are_pending() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(0, "2026.01.14\n")
+                .prints(0, "Checking...\n2026.02.03\n"),
+        );
+
+        assert_eq!(
+            hytale(&host).check(LIMIT).await.unwrap(),
+            UpdateCheck {
+                installed: Some("2026.01.14".to_string()),
+                available: Some("2026.02.03".to_string()),
+                pending: true,
+            }
+        );
+        assert_eq!(
+            host.calls()[1],
+            "su - gs_hytale -c ./hytale-downloader -print-version"
+        );
+    }
+
+    #[tokio::test]
+    async fn matching_versions_are_up_to_date() {
+        let host = Arc::new(
+            ScriptedRunner::new()
+                .prints(0, "2026.02.03")
+                .prints(0, "2026.02.03"),
+        );
+
+        assert!(!hytale(&host).check(LIMIT).await.unwrap().pending);
+    }
+
+    #[tokio::test]
+    async fn an_unknown_latest_version_fails() {
+        let host = Arc::new(ScriptedRunner::new().prints(0, "2026.02.03").exits(1));
+
+        assert!(hytale(&host).check(LIMIT).await.is_err());
+    }
+}
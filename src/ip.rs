@@ -0,0 +1,144 @@
+//! The host's public IP, looked up in the background and cached so replies
+//! never wait on an HTTP request
+//!
+//! WARNING! do not show this to anyone untrusted in the discord guild
+use crate::config::IpConfig;
+use crate::store;
+
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Seconds to wait on each provider before trying the next
+const PROVIDER_TIMEOUT: u64 = 10;
+
+/// Where the last known IP is kept, so a change while the bot was down is
+/// still noticed
+const STATE_KEY: &str = "public_ip";
+
+/// Set once at startup
+static CONFIG: OnceLock<IpConfig> = OnceLock::new();
+
+/// The last IP found
+static CACHE: Mutex<Option<IpAddr>> = Mutex::new(None);
+
+/// The IP changed from one address to another
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub old: IpAddr,
+    pub new: IpAddr,
+}
+
+impl Change {
+    /// Compare a fresh lookup against the last known IP
+    fn between(old: Option<IpAddr>, new: IpAddr) -> Option<Self> {
+        old.filter(|old| *old != new).map(|old| Change { old, new })
+    }
+}
+
+/// Remember the providers, and the IP from before the last shutdown
+pub fn init(config: &IpConfig) {
+    let _ = CONFIG.set(config.clone());
+
+    let Some(store) = store::get() else {
+        return;
+    };
+    match store.state(STATE_KEY) {
+        Ok(ip) => *CACHE.lock().unwrap() = ip.as_deref().and_then(parse),
+        Err(e) => eprintln!("Failed to load the last public IP: {e}"),
+    }
+}
+
+/// How long a lookup is trusted
+pub fn ttl() -> Duration {
+    let minutes = CONFIG
+        .get()
+        .map_or(IpConfig::default().ttl, |config| config.ttl);
+    Duration::from_secs(minutes * 60)
+}
+
+/// The cached public IP, without looking it up
+pub fn public_ip() -> String {
+    match *CACHE.lock().unwrap() {
+        Some(ip) => ip.to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Look the IP up again, returning what changed. The monitor task calls
+/// this every [ttl].
+pub async fn refresh() -> Option<Change> {
+    let providers = CONFIG.get().map_or_else(
+        || IpConfig::default().providers,
+        |config| config.providers.clone(),
+    );
+
+    let ip = match tokio::task::spawn_blocking(move || lookup(&providers)).await {
+        Ok(Some(ip)) => ip,
+        Ok(None) => {
+            eprintln!("Failed to look up the public IP from any provider");
+            return None;
+        }
+        Err(e) => {
+            eprintln!("Public IP lookup panicked: {e}");
+            return None;
+        }
+    };
+
+    let old = CACHE.lock().unwrap().replace(ip);
+    store::record("public IP", |store| {
+        store.set_state(STATE_KEY, &ip.to_string())
+    });
+    Change::between(old, ip)
+}
+
+/// Ask each provider in turn until one gives a usable answer
+fn lookup(providers: &[String]) -> Option<IpAddr> {
+    providers.iter().find_map(|provider| {
+        let response = minreq::get(provider)
+            .with_timeout(PROVIDER_TIMEOUT)
+            .send()
+            .inspect_err(|e| eprintln!("Public IP provider {provider} failed: {e}"))
+            .ok()?;
+
+        let ip = response.as_str().ok().and_then(parse);
+        if ip.is_none() {
+            eprintln!("Public IP provider {provider} did not answer with an IP");
+        }
+        ip
+    })
+}
+
+/// Providers answer in plain text, some with a trailing newline
+fn parse(body: &str) -> Option<IpAddr> {
+    body.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_answers_are_validated() {
+        assert_eq!(parse("203.0.113.7\n"), "203.0.113.7".parse().ok());
+        assert_eq!(parse("2001:db8::1"), "2001:db8::1".parse().ok());
+        assert_eq!(parse("<html>rate limited</html>"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn only_a_different_ip_is_a_change() {
+        let first: IpAddr = "203.0.113.7".parse().unwrap();
+        let second: IpAddr = "203.0.113.8".parse().unwrap();
+
+        assert_eq!(Change::between(None, first), None);
+        assert_eq!(Change::between(Some(first), first), None);
+        assert_eq!(
+            Change::between(Some(first), second),
+            Some(Change {
+                old: first,
+                new: second
+            })
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! One operation at a time per server, so e.g. a `/start` can not launch the
+//! server while steamcmd is still rewriting its files for an `/update`
+use crate::games::format_duration;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// The operation running on each server, by server name
+static LOCKS: LazyLock<Mutex<HashMap<String, Holder>>> = LazyLock::new(Default::default);
+
+/// Who is running what
+#[derive(Debug, Clone)]
+struct Holder {
+    verb: String,
+    actor: String,
+    since: Instant,
+}
+
+/// Held for as long as the operation runs, freeing the server when dropped
+#[derive(Debug)]
+pub struct Lock {
+    game: String,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        LOCKS.lock().unwrap().remove(&self.game);
+    }
+}
+
+/// The server is already busy with another operation
+#[derive(Debug)]
+pub struct Busy {
+    game: String,
+    verb: String,
+    actor: String,
+    elapsed: Duration,
+}
+
+impl fmt::Display for Busy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is currently {} (started by {} {} ago)",
+            self.game,
+            ongoing(&self.verb),
+            self.actor,
+            format_duration(self.elapsed)
+        )
+    }
+}
+
+/// Take the server for `verb`, unless something else already has it
+pub fn acquire(game: &str, verb: &str, actor: &str) -> Result<Lock, Busy> {
+    let mut locks = LOCKS.lock().unwrap();
+
+    if let Some(holder) = locks.get(game) {
+        return Err(Busy {
+            game: game.to_string(),
+            verb: holder.verb.clone(),
+            actor: holder.actor.clone(),
+            elapsed: holder.since.elapsed(),
+        });
+    }
+
+    locks.insert(
+        game.to_string(),
+        Holder {
+            verb: verb.to_string(),
+            actor: actor.to_string(),
+            since: Instant::now(),
+        },
+    );
+    Ok(Lock {
+        game: game.to_string(),
+    })
+}
+
+/// e.g. "updating" for "update", "backing up" for "back up"
+fn ongoing(verb: &str) -> String {
+    let (first, rest) = verb.split_once(' ').unwrap_or((verb, ""));
+
+    let stem = match first {
+        "stop" => "stopp",
+        _ => first.strip_suffix('e').unwrap_or(first),
+    };
+
+    format!("{stem}ing {rest}").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_second_operation_is_turned_away_until_the_first_ends() {
+        let lock = acquire("locks-test", "update", "tony").unwrap();
+
+        let busy = acquire("locks-test", "start", "sam").unwrap_err();
+        assert_eq!(
+            busy.to_string(),
+            "locks-test is currently updating (started by tony 0m ago)"
+        );
+
+        drop(lock);
+        assert!(acquire("locks-test", "start", "sam").is_ok());
+    }
+
+    #[test]
+    fn other_servers_are_not_blocked() {
+        let _lock = acquire("locks-test-a", "stop", "tony").unwrap();
+        assert!(acquire("locks-test-b", "stop", "tony").is_ok());
+    }
+
+    #[test]
+    fn verbs_read_as_ongoing() {
+        assert_eq!(ongoing("start"), "starting");
+        assert_eq!(ongoing("stop"), "stopping");
+        assert_eq!(ongoing("restart"), "restarting");
+        assert_eq!(ongoing("update"), "updating");
+        assert_eq!(ongoing("back up"), "backing up");
+        assert_eq!(ongoing("restore"), "restoring");
+    }
+}
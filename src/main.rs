@@ -1,6 +1,16 @@
+mod audit;
 mod commands;
+mod config;
 mod events;
 mod games;
+mod ip;
+mod locks;
+mod permissions;
+mod query;
+mod rcon;
+mod schedule;
+mod store;
+mod tasks;
 mod tokens;
 
 // discord API
@@ -9,9 +19,36 @@ use serenity::all::GatewayIntents;
 
 #[tokio::main]
 async fn main() {
+    // load the game servers before anything tries to register commands
+    let config = match config::load() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Invalid server config: {error}");
+            std::process::exit(1);
+        }
+    };
+    if let Err(error) = store::init() {
+        eprintln!("Could not open the database: {error}");
+        std::process::exit(1);
+    }
+    games::init(&config);
+    ip::init(&config.ip);
+    schedule::init(&config);
+
+    if tokens::ADMIN_ROLE_ID.is_none() {
+        println!("No admin role is set, so commands for the `admin` role are disabled");
+    }
+
     // build the client
     let mut client = Client::builder(tokens::BOT_TOKEN.as_str(), GatewayIntents::empty())
-        .event_handler(events::Handler)
+        .event_handler(events::Handler {
+            permissions: permissions::Permissions::new(
+                &config,
+                *tokens::TRUSTED_ROLE_ID,
+                *tokens::ADMIN_ROLE_ID,
+            ),
+            config,
+        })
         .await
         .expect("Error creating client");
 
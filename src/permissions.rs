@@ -0,0 +1,235 @@
+//! Which roles may use which commands, on which servers
+//!
+//! Anything not set in `[permissions]` keeps the old behaviour: admins for
+//! the console, restores and schedule changes, trusted members for the rest.
+use crate::config::{self, Config, Verb};
+
+use serenity::model::id::RoleId;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Role names allowed each verb, resolved against discord role ids
+pub struct Permissions {
+    /// Role ids by name, including `trusted` and `admin`
+    roles: HashMap<String, RoleId>,
+    global: config::Permissions,
+    /// Extra roles per server name
+    servers: HashMap<String, config::Permissions>,
+}
+
+/// Why a member was turned away, naming the roles that would have worked
+#[derive(Debug, PartialEq, Eq)]
+pub struct Denied {
+    pub verb: Verb,
+    pub game: Option<String>,
+    pub roles: Vec<String>,
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match &self.game {
+            Some(game) => format!("{} {game}", self.verb.name()),
+            None => format!("use /{}", self.verb.name()),
+        };
+
+        match self.roles.as_slice() {
+            [] => write!(f, "Nobody is allowed to {action}"),
+            [role] => write!(f, "You need the `{role}` role to {action}"),
+            roles => write!(
+                f,
+                "You need one of the `{}` roles to {action}",
+                roles.join("`, `")
+            ),
+        }
+    }
+}
+
+impl Permissions {
+    /// Resolve the config against the ids of the built in roles. Without an
+    /// admin role nobody has `admin`, so anything left to it is disabled.
+    pub fn new(config: &Config, trusted: RoleId, admin: Option<RoleId>) -> Self {
+        let mut roles: HashMap<String, RoleId> = config
+            .roles
+            .iter()
+            .map(|(name, id)| (name.clone(), RoleId::new(*id)))
+            .collect();
+        roles.insert("trusted".to_string(), trusted);
+        if let Some(admin) = admin {
+            roles.insert("admin".to_string(), admin);
+        }
+
+        let servers = config
+            .enabled_servers()
+            .map(|server| (server.name.clone(), server.permissions.clone()))
+            .collect();
+
+        Permissions {
+            roles,
+            global: config.permissions.clone(),
+            servers,
+        }
+    }
+
+    /// Names of every role allowed the verb, on a server if given
+    pub fn allowed_roles(&self, verb: Verb, game: Option<&str>) -> Vec<String> {
+        let mut allowed = match self.global.get(&verb) {
+            Some(roles) => roles.clone(),
+            None => vec![default_role(verb).to_string()],
+        };
+
+        let extra = game
+            .and_then(|game| self.servers.get(game))
+            .and_then(|permissions| permissions.get(&verb));
+        for role in extra.into_iter().flatten() {
+            if !allowed.contains(role) {
+                allowed.push(role.clone());
+            }
+        }
+
+        allowed
+    }
+
+    /// Check a member with `member_roles` can use the verb
+    pub fn check(
+        &self,
+        verb: Verb,
+        game: Option<&str>,
+        member_roles: &[RoleId],
+    ) -> Result<(), Denied> {
+        let allowed = self.allowed_roles(verb, game);
+
+        let permitted = allowed.iter().any(|name| {
+            name == "everyone"
+                || self
+                    .roles
+                    .get(name)
+                    .is_some_and(|id| member_roles.contains(id))
+        });
+
+        if permitted {
+            Ok(())
+        } else {
+            Err(Denied {
+                verb,
+                game: game.map(str::to_string),
+                roles: allowed,
+            })
+        }
+    }
+}
+
+/// Who may do something when the config does not say
+fn default_role(verb: Verb) -> &'static str {
+    match verb {
+        Verb::Console | Verb::Restore | Verb::Schedule => "admin",
+        _ => "trusted",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRUSTED: RoleId = RoleId::new(1);
+    const ADMIN: RoleId = RoleId::new(2);
+    const SKYVAULTS_ADMINS: RoleId = RoleId::new(3);
+    const HOST_ADMIN: RoleId = RoleId::new(4);
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [roles]
+            skyvaults-admins = 3
+            host-admin       = 4
+
+            [permissions]
+            start   = ["everyone"]
+            list    = ["everyone"]
+            restart = ["host-admin"]
+            update  = ["host-admin"]
+            restore = ["host-admin"]
+
+            [[servers]]
+            name        = "skyvaults"
+            description = "Skyvaults"
+            port        = 25565
+            user        = "gs_skyvault"
+            help        = "help"
+            backend     = { kind = "lgsm", runner = "mcserver" }
+            permissions = { restart = ["skyvaults-admins"] }
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn permissions() -> Permissions {
+        Permissions::new(&config(), TRUSTED, Some(ADMIN))
+    }
+
+    /// Whether a member with `roles` may use the verb on skyvaults
+    fn on_skyvaults(verb: Verb, roles: &[RoleId]) -> bool {
+        permissions().check(verb, Some("skyvaults"), roles).is_ok()
+    }
+
+    #[test]
+    fn everyone_can_use_open_commands() {
+        assert!(permissions().check(Verb::List, None, &[]).is_ok());
+        assert!(on_skyvaults(Verb::Start, &[]));
+    }
+
+    #[test]
+    fn unset_verbs_keep_their_defaults() {
+        assert!(on_skyvaults(Verb::Stop, &[TRUSTED]));
+        assert!(!on_skyvaults(Verb::Stop, &[]));
+        assert!(!on_skyvaults(Verb::Console, &[TRUSTED]));
+        assert!(on_skyvaults(Verb::Console, &[ADMIN]));
+    }
+
+    #[test]
+    fn admin_commands_are_disabled_without_an_admin_role() {
+        let permissions = Permissions::new(&config(), TRUSTED, None);
+
+        assert!(
+            permissions
+                .check(Verb::Stop, Some("skyvaults"), &[TRUSTED])
+                .is_ok()
+        );
+        assert!(
+            permissions
+                .check(Verb::Console, Some("skyvaults"), &[TRUSTED, ADMIN])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn server_roles_only_apply_to_their_server() {
+        let permissions = permissions();
+        let restart = |game, roles: &[RoleId]| permissions.check(Verb::Restart, Some(game), roles);
+
+        assert!(restart("skyvaults", &[SKYVAULTS_ADMINS]).is_ok());
+        assert!(restart("zomboid", &[SKYVAULTS_ADMINS]).is_err());
+        assert!(restart("zomboid", &[HOST_ADMIN]).is_ok());
+    }
+
+    #[test]
+    fn denials_name_the_roles_needed() {
+        let permissions = permissions();
+
+        let update = permissions
+            .check(Verb::Update, Some("skyvaults"), &[TRUSTED])
+            .unwrap_err();
+        let restart = permissions
+            .check(Verb::Restart, Some("skyvaults"), &[TRUSTED])
+            .unwrap_err();
+
+        assert_eq!(
+            update.to_string(),
+            "You need the `host-admin` role to update skyvaults"
+        );
+        assert_eq!(
+            restart.to_string(),
+            "You need one of the `host-admin`, `skyvaults-admins` roles to restart skyvaults"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Valve A2S_INFO/A2S_PLAYER queries over UDP
+//!
+//! See <https://developer.valvesoftware.com/wiki/Server_queries>
+
+use crate::query::{QueryError, ServerInfo};
+
+use tokio::net::UdpSocket;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Every simple (non-split) packet starts with this
+const SIMPLE_HEADER: [u8; 4] = [0xFF; 4];
+
+const A2S_INFO: u8 = 0x54;
+const A2S_PLAYER: u8 = 0x55;
+const S2C_CHALLENGE: u8 = 0x41;
+const INFO_RESPONSE: u8 = 0x49;
+const PLAYER_RESPONSE: u8 = 0x44;
+
+/// How long the player list gets once the info is in, kept short as plenty
+/// of servers never answer A2S_PLAYER at all
+const PLAYERS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Ask a Source engine style server for its info and player list
+pub async fn query(addr: SocketAddr, timeout: Duration) -> Result<ServerInfo, QueryError> {
+    let bind: SocketAddr = if addr.is_ipv4() {
+        "0.0.0.0:0".parse().unwrap()
+    } else {
+        "[::]:0".parse().unwrap()
+    };
+
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(addr).await?;
+
+    let mut info = tokio::time::timeout(timeout, query_info(&socket))
+        .await
+        .map_err(|_| QueryError::Timeout)??;
+
+    // player names are a nice extra, the counts already came with the info
+    if let Ok(Ok(names)) = tokio::time::timeout(PLAYERS_TIMEOUT, query_players(&socket)).await {
+        info.player_names = names;
+    }
+
+    Ok(info)
+}
+
+async fn query_info(socket: &UdpSocket) -> Result<ServerInfo, QueryError> {
+    let mut request = SIMPLE_HEADER.to_vec();
+    request.push(A2S_INFO);
+    request.extend_from_slice(b"Source Engine Query\0");
+
+    let response = exchange(socket, &request, None).await?;
+    let mut reader = Reader::new(&response);
+
+    if reader.u8()? != INFO_RESPONSE {
+        return Err(QueryError::Malformed("unexpected A2S_INFO response type"));
+    }
+
+    let _protocol = reader.u8()?;
+    let _name = reader.string()?;
+    let map = reader.string()?;
+    let _folder = reader.string()?;
+    let _game = reader.string()?;
+    let _app_id = reader.u16()?;
+    let players = reader.u8()?;
+    let max_players = reader.u8()?;
+    let _bots = reader.u8()?;
+    let _server_type = reader.u8()?;
+    let _environment = reader.u8()?;
+    let _visibility = reader.u8()?;
+    let _vac = reader.u8()?;
+    let version = reader.string()?;
+
+    Ok(ServerInfo {
+        players: players.into(),
+        max_players: max_players.into(),
+        player_names: Vec::new(),
+        map: Some(map).filter(|m| !m.is_empty()),
+        version: Some(version).filter(|v| !v.is_empty()),
+        motd: None,
+    })
+}
+
+async fn query_players(socket: &UdpSocket) -> Result<Vec<String>, QueryError> {
+    let mut request = SIMPLE_HEADER.to_vec();
+    request.push(A2S_PLAYER);
+
+    // an all 0xFF challenge asks the server to hand out a real one
+    let response = exchange(socket, &request, Some([0xFF; 4])).await?;
+    let mut reader = Reader::new(&response);
+
+    if reader.u8()? != PLAYER_RESPONSE {
+        return Err(QueryError::Malformed("unexpected A2S_PLAYER response type"));
+    }
+
+    let count = reader.u8()?;
+    let mut names = Vec::with_capacity(count.into());
+    for _ in 0..count {
+        let _index = reader.u8()?;
+        let name = reader.string()?;
+        let _score = reader.bytes(4)?;
+        let _duration = reader.bytes(4)?;
+
+        // connecting players show up with an empty name
+        if !name.is_empty() {
+            names.push(name);
+        }
+    }
+
+    Ok(names)
+}
+
+/// Send a request and return the response payload, answering a challenge if
+/// the server asks for one
+async fn exchange(
+    socket: &UdpSocket,
+    request: &[u8],
+    challenge: Option<[u8; 4]>,
+) -> Result<Vec<u8>, QueryError> {
+    let mut packet = request.to_vec();
+    if let Some(challenge) = challenge {
+        packet.extend_from_slice(&challenge);
+    }
+
+    let response = round_trip(socket, &packet).await?;
+
+    if response.first() != Some(&S2C_CHALLENGE) {
+        return Ok(response);
+    }
+
+    let challenge = response
+        .get(1..5)
+        .ok_or(QueryError::Malformed("truncated challenge"))?;
+
+    let mut packet = request.to_vec();
+    packet.extend_from_slice(challenge);
+
+    let response = round_trip(socket, &packet).await?;
+    if response.first() == Some(&S2C_CHALLENGE) {
+        return Err(QueryError::Malformed("server kept sending challenges"));
+    }
+
+    Ok(response)
+}
+
+/// One request/response, with the simple packet header stripped
+async fn round_trip(socket: &UdpSocket, packet: &[u8]) -> Result<Vec<u8>, QueryError> {
+    socket.send(packet).await?;
+
+    let mut buffer = vec![0; 1400];
+    let len = socket.recv(&mut buffer).await?;
+    buffer.truncate(len);
+
+    match buffer.strip_prefix(&SIMPLE_HEADER) {
+        Some(payload) => Ok(payload.to_vec()),
+        None => Err(QueryError::Malformed("split or unrecognised packet header")),
+    }
+}
+
+/// Little-endian reader over a response payload
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], QueryError> {
+        if self.data.len() < len {
+            return Err(QueryError::Malformed("response ended early"));
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, QueryError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, QueryError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Null terminated string, decoded lossily since names can be anything
+    fn string(&mut self) -> Result<String, QueryError> {
+        let end = self
+            .data
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or(QueryError::Malformed("unterminated string"))?;
+
+        let value = String::from_utf8_lossy(&self.data[..end]).into_owned();
+        self.data = &self.data[end + 1..];
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHALLENGE: [u8; 4] = [0x12, 0x34, 0x56, 0x78];
+
+    /// Fake server that insists on a challenge before answering anything,
+    /// and never answers A2S_PLAYER without `players`
+    async fn fake_server(players: Option<Vec<&'static str>>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buffer = [0; 1400];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buffer).await.unwrap();
+                let request = &buffer[..len];
+                let challenged = request.ends_with(&CHALLENGE);
+
+                let names = players.as_deref().unwrap_or_default();
+                let mut response = SIMPLE_HEADER.to_vec();
+                if !challenged {
+                    response.push(S2C_CHALLENGE);
+                    response.extend_from_slice(&CHALLENGE);
+                } else if request[4] == A2S_INFO {
+                    response.push(INFO_RESPONSE);
+                    response.push(17);
+                    response
+                        .extend_from_slice(b"Brumders\0Navezgane\x007DaysToDie\x007 Days To Die\0");
+                    response.extend_from_slice(&251570u32.to_le_bytes()[..2]);
+                    response.extend_from_slice(&[names.len() as u8, 8, 0, b'd', b'l', 1, 1]);
+                    response.extend_from_slice(b"V 2.1 (b14)\0");
+                } else if players.is_none() {
+                    continue;
+                } else {
+                    response.push(PLAYER_RESPONSE);
+                    response.push(names.len() as u8);
+                    for (i, name) in names.iter().enumerate() {
+                        response.push(i as u8);
+                        response.extend_from_slice(name.as_bytes());
+                        response.push(0);
+                        response.extend_from_slice(&10i32.to_le_bytes());
+                        response.extend_from_slice(&60.0f32.to_le_bytes());
+                    }
+                }
+
+                socket.send_to(&response, peer).await.unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn queries_info_and_players_through_challenges() {
+        let addr = fake_server(Some(vec!["tony", "pete", "sam"])).await;
+
+        let info = query(addr, Duration::from_secs(2)).await.unwrap();
+
+        assert_eq!(info.players, 3);
+        assert_eq!(info.max_players, 8);
+        assert_eq!(info.map.as_deref(), Some("Navezgane"));
+        assert_eq!(info.version.as_deref(), Some("V 2.1 (b14)"));
+        assert_eq!(info.player_names, ["tony", "pete", "sam"]);
+    }
+
+    #[tokio::test]
+    async fn info_is_kept_without_a_player_list() {
+        let addr = fake_server(None).await;
+
+        let info = query(addr, Duration::from_secs(2)).await.unwrap();
+
+        assert_eq!(info.max_players, 8);
+        assert_eq!(info.version.as_deref(), Some("V 2.1 (b14)"));
+        assert!(info.player_names.is_empty());
+    }
+
+    #[tokio::test]
+    async fn silent_servers_time_out() {
+        // bound but never answers
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        let result = query(addr, Duration::from_millis(200)).await;
+
+        assert!(matches!(result, Err(QueryError::Timeout)));
+    }
+
+    #[test]
+    fn truncated_strings_are_malformed() {
+        let mut reader = Reader::new(b"no terminator");
+        assert!(matches!(reader.string(), Err(QueryError::Malformed(_))));
+    }
+}
@@ -0,0 +1,270 @@
+//! Minecraft Server List Ping (handshake + status request) over TCP
+//!
+//! See <https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping>
+
+use crate::query::{QueryError, ServerInfo};
+
+use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Modded servers send a lot of mod info, but anything past this is silly
+const MAX_RESPONSE_LEN: usize = 4 * 1024 * 1024;
+
+/// Protocol version sent in the handshake, -1 by convention for pings
+const PING_PROTOCOL: i32 = -1;
+
+/// The parts of the status JSON worth showing
+#[derive(Debug, Deserialize)]
+struct StatusResponse {
+    version: Option<Version>,
+    players: Option<Players>,
+    description: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Players {
+    max: u32,
+    online: u32,
+    #[serde(default)]
+    sample: Vec<Player>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Player {
+    name: String,
+}
+
+/// Ping a minecraft java server for its status
+pub async fn query(addr: SocketAddr, timeout: Duration) -> Result<ServerInfo, QueryError> {
+    tokio::time::timeout(timeout, ping(addr))
+        .await
+        .map_err(|_| QueryError::Timeout)?
+}
+
+async fn ping(addr: SocketAddr) -> Result<ServerInfo, QueryError> {
+    let mut stream = TcpStream::connect(addr).await?;
+
+    // handshake, asking for the status state
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, 0x00);
+    write_varint(&mut handshake, PING_PROTOCOL);
+    write_string(&mut handshake, &addr.ip().to_string());
+    handshake.extend_from_slice(&addr.port().to_be_bytes());
+    write_varint(&mut handshake, 1);
+
+    stream.write_all(&frame(&handshake)).await?;
+    stream.write_all(&frame(&[0x00])).await?;
+
+    let len = read_varint(&mut stream).await?;
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= MAX_RESPONSE_LEN)
+        .ok_or(QueryError::Malformed("unreasonable packet length"))?;
+
+    let mut packet = vec![0; len];
+    stream.read_exact(&mut packet).await?;
+
+    let mut packet = packet.as_slice();
+    if read_varint(&mut packet).await? != 0x00 {
+        return Err(QueryError::Malformed(
+            "unexpected status response packet id",
+        ));
+    }
+
+    let json_len = read_varint(&mut packet).await?;
+    let json = usize::try_from(json_len)
+        .ok()
+        .and_then(|len| packet.get(..len))
+        .ok_or(QueryError::Malformed("status string longer than packet"))?;
+
+    let status: StatusResponse = serde_json::from_slice(json)
+        .map_err(|_| QueryError::Malformed("status is not the expected json"))?;
+
+    Ok(status.into())
+}
+
+impl From<StatusResponse> for ServerInfo {
+    fn from(status: StatusResponse) -> Self {
+        let (players, max_players, player_names) = match status.players {
+            Some(p) => (
+                p.online,
+                p.max,
+                p.sample.into_iter().map(|player| player.name).collect(),
+            ),
+            None => (0, 0, Vec::new()),
+        };
+
+        ServerInfo {
+            players,
+            max_players,
+            player_names,
+            map: None,
+            version: status.version.map(|v| strip_formatting(&v.name)),
+            motd: status
+                .description
+                .map(|d| strip_formatting(&flatten_text(&d)))
+                .filter(|motd| !motd.trim().is_empty()),
+        }
+    }
+}
+
+/// Prefix a packet with its length
+fn frame(packet: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(packet.len() + 5);
+    write_varint(&mut framed, packet.len() as i32);
+    framed.extend_from_slice(packet);
+    framed
+}
+
+fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buffer.push(value as u8);
+            return;
+        }
+        buffer.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    write_varint(buffer, value.len() as i32);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+async fn read_varint<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<i32, QueryError> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = reader.read_u8().await?;
+        value |= u32::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+
+    Err(QueryError::Malformed("varint is too long"))
+}
+
+/// The MOTD is either plain text or a chat component with nested extras
+fn flatten_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(parts) => parts.iter().map(flatten_text).collect(),
+        serde_json::Value::Object(component) => {
+            let mut text = component.get("text").map(flatten_text).unwrap_or_default();
+            if let Some(extra) = component.get("extra") {
+                text.push_str(&flatten_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+/// Drop the legacy `§` colour/format codes
+fn strip_formatting(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Fake server that answers a single status request with `json`
+    async fn fake_server(json: &'static str) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            // handshake then status request
+            for _ in 0..2 {
+                let len = read_varint(&mut stream).await.unwrap();
+                let mut packet = vec![0; len as usize];
+                stream.read_exact(&mut packet).await.unwrap();
+            }
+
+            let mut response = Vec::new();
+            write_varint(&mut response, 0x00);
+            write_string(&mut response, json);
+            stream.write_all(&frame(&response)).await.unwrap();
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn pings_for_players_version_and_motd() {
+        let addr = fake_server(
+            r#"{
+                "version": {"name": "1.18.2", "protocol": 758},
+                "players": {"max": 10, "online": 2, "sample": [
+                    {"name": "tony", "id": "00000000-0000-0000-0000-000000000000"},
+                    {"name": "pete", "id": "00000000-0000-0000-0000-000000000001"}
+                ]},
+                "description": {"text": "§6Brumders ", "extra": [{"text": "Skyvaults"}]},
+                "forgeData": {"mods": []}
+            }"#,
+        )
+        .await;
+
+        let info = query(addr, Duration::from_secs(2)).await.unwrap();
+
+        assert_eq!(info.players, 2);
+        assert_eq!(info.max_players, 10);
+        assert_eq!(info.player_names, ["tony", "pete"]);
+        assert_eq!(info.version.as_deref(), Some("1.18.2"));
+        assert_eq!(info.motd.as_deref(), Some("Brumders Skyvaults"));
+    }
+
+    #[tokio::test]
+    async fn plain_text_descriptions_work() {
+        let addr =
+            fake_server(r#"{"players": {"max": 8, "online": 0}, "description": "hi"}"#).await;
+
+        let info = query(addr, Duration::from_secs(2)).await.unwrap();
+
+        assert_eq!(info.players, 0);
+        assert!(info.player_names.is_empty());
+        assert_eq!(info.motd.as_deref(), Some("hi"));
+    }
+
+    #[tokio::test]
+    async fn closed_ports_fail() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        assert!(query(addr, Duration::from_secs(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 25565, -1, i32::MAX] {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, value);
+            assert_eq!(read_varint(&mut buffer.as_slice()).await.unwrap(), value);
+        }
+    }
+}
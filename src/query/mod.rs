@@ -0,0 +1,112 @@
+//! Live server details straight from the game, rather than the host
+mod a2s;
+mod minecraft;
+mod satisfactory;
+
+use crate::config::{QueryConfig, QueryProtocol};
+
+use std::time::Duration;
+use std::{env, fmt, io};
+
+/// How long a game gets to answer a query
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What the game itself says about the server
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub players: u32,
+    pub max_players: u32,
+    /// Names of connected players, where the game shares them
+    pub player_names: Vec<String>,
+    pub map: Option<String>,
+    pub version: Option<String>,
+    /// Message of the day, where the game has one
+    pub motd: Option<String>,
+}
+
+impl ServerInfo {
+    /// e.g. "3/8 players"
+    pub fn player_summary(&self) -> String {
+        format!("{}/{} players", self.players, self.max_players)
+    }
+
+    /// Markdown list of everything the game shared, for help messages
+    pub fn details(&self) -> String {
+        let mut details = String::new();
+
+        if let Some(version) = &self.version {
+            details.push_str(&format!("- Version: {version}\n"));
+        }
+
+        if let Some(map) = &self.map {
+            details.push_str(&format!("- Map: {map}\n"));
+        }
+
+        if let Some(motd) = &self.motd {
+            details.push_str(&format!("- MOTD: {}\n", motd.trim()));
+        }
+
+        details.push_str(&format!("- Online: {}", self.player_summary()));
+        if !self.player_names.is_empty() {
+            details.push_str(&format!(" ({})", self.player_names.join(", ")));
+        }
+        details.push('\n');
+
+        details
+    }
+}
+
+/// Reasons a game did not answer a query
+#[derive(Debug)]
+pub enum QueryError {
+    Io(io::Error),
+    /// Nothing came back in time, usually because the server is still loading
+    Timeout,
+    /// Something came back, but not what the protocol says it should be
+    Malformed(&'static str),
+    /// The game wants a token, and it was missing or rejected
+    Unauthorised,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Io(e) => write!(f, "{e}"),
+            QueryError::Timeout => write!(f, "no response"),
+            QueryError::Malformed(reason) => write!(f, "malformed response: {reason}"),
+            QueryError::Unauthorised => write!(f, "the query token was missing or rejected"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<io::Error> for QueryError {
+    fn from(e: io::Error) -> Self {
+        QueryError::Io(e)
+    }
+}
+
+/// Query a game server with whatever protocol it speaks
+///
+/// `default_port` is used when the config does not set a query port
+pub async fn query(config: &QueryConfig, default_port: u16) -> Result<ServerInfo, QueryError> {
+    let port = config.port.unwrap_or(default_port);
+    let addr = tokio::net::lookup_host((config.host.as_str(), port))
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "query host did not resolve"))?;
+
+    match config.protocol {
+        QueryProtocol::A2s => a2s::query(addr, QUERY_TIMEOUT).await,
+        QueryProtocol::Minecraft => minecraft::query(addr, QUERY_TIMEOUT).await,
+        QueryProtocol::Satisfactory => {
+            let token = config
+                .token_env
+                .as_ref()
+                .and_then(|name| env::var(name).ok())
+                .ok_or(QueryError::Unauthorised)?;
+            satisfactory::query(addr, &token, QUERY_TIMEOUT).await
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Satisfactory dedicated server HTTPS API, which shares the game port
+//!
+//! See the `DedicatedServerAPIDocs.md` shipped with the server files. The
+//! server generates its own self-signed certificate, so it is not verified.
+
+use crate::query::{QueryError, ServerInfo};
+
+use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The API answers with a small JSON document, anything bigger is wrong
+const MAX_RESPONSE_LEN: u64 = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    data: Data,
+}
+
+#[derive(Debug, Deserialize)]
+struct Data {
+    #[serde(rename = "serverGameState")]
+    state: GameState,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GameState {
+    active_session_name: String,
+    num_connected_players: u32,
+    player_limit: u32,
+}
+
+/// Ask the server for its game state, authorised with an API token from
+/// `server.GenerateAPIToken`
+pub async fn query(
+    addr: SocketAddr,
+    token: &str,
+    timeout: Duration,
+) -> Result<ServerInfo, QueryError> {
+    tokio::time::timeout(timeout, query_state(addr, token))
+        .await
+        .map_err(|_| QueryError::Timeout)?
+}
+
+async fn query_state(addr: SocketAddr, token: &str) -> Result<ServerInfo, QueryError> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|_| QueryError::Malformed("no usable TLS versions"))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(SelfSigned(provider)))
+        .with_no_client_auth();
+
+    let stream = TcpStream::connect(addr).await?;
+    let mut stream = TlsConnector::from(Arc::new(config))
+        .connect(ServerName::IpAddress(addr.ip().into()), stream)
+        .await?;
+
+    let body = r#"{"function":"QueryServerState"}"#;
+    let request = format!(
+        "POST /api/v1 HTTP/1.1\r\n\
+        Host: {addr}\r\n\
+        Authorization: Bearer {token}\r\n\
+        Content-Type: application/json\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    (&mut stream)
+        .take(MAX_RESPONSE_LEN)
+        .read_to_end(&mut response)
+        .await?;
+
+    parse_response(&response)
+}
+
+/// Pull the game state out of a raw HTTP response
+fn parse_response(response: &[u8]) -> Result<ServerInfo, QueryError> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or(QueryError::Malformed("no end to the HTTP headers"))?;
+
+    let headers = String::from_utf8_lossy(&response[..split]).to_ascii_lowercase();
+    let mut body = response[split + 4..].to_vec();
+
+    match headers.split_whitespace().nth(1) {
+        Some("200") => {}
+        Some("401") | Some("403") => return Err(QueryError::Unauthorised),
+        _ => return Err(QueryError::Malformed("unexpected HTTP status")),
+    }
+
+    if headers.contains("transfer-encoding: chunked") {
+        body = dechunk(&body)?;
+    }
+
+    let response: Response = serde_json::from_slice(&body)
+        .map_err(|_| QueryError::Malformed("game state is not the expected json"))?;
+    let state = response.data.state;
+
+    Ok(ServerInfo {
+        players: state.num_connected_players,
+        max_players: state.player_limit,
+        map: Some(state.active_session_name).filter(|s| !s.is_empty()),
+        ..ServerInfo::default()
+    })
+}
+
+/// Join the chunks of a chunked transfer encoded body
+fn dechunk(mut body: &[u8]) -> Result<Vec<u8>, QueryError> {
+    let mut joined = Vec::new();
+
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or(QueryError::Malformed("unterminated chunk size"))?;
+
+        let size = std::str::from_utf8(&body[..line_end])
+            .ok()
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or(QueryError::Malformed("invalid chunk size"))?;
+
+        if size == 0 {
+            return Ok(joined);
+        }
+
+        let chunk = body
+            .get(line_end + 2..line_end + 2 + size)
+            .ok_or(QueryError::Malformed("chunk longer than the response"))?;
+        joined.extend_from_slice(chunk);
+
+        body = body.get(line_end + 4 + size..).unwrap_or_default();
+    }
+}
+
+/// Accepts the server's self-signed certificate, while still checking the
+/// handshake signatures so the connection itself is sound
+#[derive(Debug)]
+struct SelfSigned(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SelfSigned {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATE: &str = r#"{"data":{"serverGameState":{"activeSessionName":"Brumders","numConnectedPlayers":2,"playerLimit":4,"techTier":5,"isGameRunning":true}}}"#;
+
+    #[test]
+    fn reads_players_from_the_game_state() {
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{STATE}",
+            STATE.len()
+        );
+
+        let info = parse_response(response.as_bytes()).unwrap();
+
+        assert_eq!(info.players, 2);
+        assert_eq!(info.max_players, 4);
+        assert_eq!(info.map.as_deref(), Some("Brumders"));
+    }
+
+    #[test]
+    fn reads_chunked_responses() {
+        let (first, second) = STATE.split_at(20);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{first}\r\n{:x}\r\n{second}\r\n0\r\n\r\n",
+            first.len(),
+            second.len()
+        );
+
+        let info = parse_response(response.as_bytes()).unwrap();
+
+        assert_eq!(info.players, 2);
+    }
+
+    #[test]
+    fn rejected_tokens_are_unauthorised() {
+        let response = b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n";
+
+        assert!(matches!(
+            parse_response(response),
+            Err(QueryError::Unauthorised)
+        ));
+    }
+}
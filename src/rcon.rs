@@ -0,0 +1,232 @@
+//! Source RCON client, spoken by Minecraft, Project Zomboid and most steam games
+//!
+//! See <https://developer.valvesoftware.com/wiki/Source_RCON_Protocol>
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use std::time::Duration;
+use std::{fmt, io};
+
+/// How long a game gets to log in and answer a command
+pub const RCON_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for more fragments of a long response
+const FRAGMENT_WAIT: Duration = Duration::from_millis(250);
+
+/// Largest packet the protocol allows
+const MAX_PACKET_LEN: i32 = 4096 + 10;
+
+const SERVERDATA_AUTH: i32 = 3;
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+const SERVERDATA_EXECCOMMAND: i32 = 2;
+const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// Id sent with every request, the server echoes it back
+const REQUEST_ID: i32 = 0x0B07;
+
+/// Reasons a console command did not get a response
+#[derive(Debug)]
+pub enum RconError {
+    Io(io::Error),
+    /// Nothing came back in time
+    Timeout,
+    /// The server rejected the RCON password
+    AuthFailed,
+    /// Something came back, but not what the protocol says it should be
+    Malformed(&'static str),
+}
+
+impl fmt::Display for RconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RconError::Io(e) => write!(f, "{e}"),
+            RconError::Timeout => write!(f, "no response"),
+            RconError::AuthFailed => write!(f, "the RCON password was rejected"),
+            RconError::Malformed(reason) => write!(f, "malformed response: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RconError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RconError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RconError {
+    fn from(e: io::Error) -> Self {
+        RconError::Io(e)
+    }
+}
+
+/// Log in and run a single console command, returning whatever it printed
+pub async fn execute(
+    host: &str,
+    port: u16,
+    password: &str,
+    command: &str,
+    timeout: Duration,
+) -> Result<String, RconError> {
+    tokio::time::timeout(timeout, async {
+        let mut stream = TcpStream::connect((host, port)).await?;
+        authenticate(&mut stream, password).await?;
+
+        send(&mut stream, SERVERDATA_EXECCOMMAND, command).await?;
+        let mut output = read_response(&mut stream).await?;
+
+        // long output is split across several packets with no end marker, so
+        // keep reading until the server goes quiet
+        while let Ok(more) = tokio::time::timeout(FRAGMENT_WAIT, read_response(&mut stream)).await {
+            match more {
+                Ok(more) => output.push_str(&more),
+                Err(_) => break,
+            }
+        }
+
+        Ok(output)
+    })
+    .await
+    .map_err(|_| RconError::Timeout)?
+}
+
+async fn authenticate(stream: &mut TcpStream, password: &str) -> Result<(), RconError> {
+    send(stream, SERVERDATA_AUTH, password).await?;
+
+    // source servers send an empty response value before the auth response
+    loop {
+        let (id, kind, _) = read_packet(stream).await?;
+        if kind != SERVERDATA_AUTH_RESPONSE {
+            continue;
+        }
+
+        return match id {
+            -1 => Err(RconError::AuthFailed),
+            REQUEST_ID => Ok(()),
+            _ => Err(RconError::Malformed("auth response for an unknown request")),
+        };
+    }
+}
+
+/// Body of the next command response
+async fn read_response(stream: &mut TcpStream) -> Result<String, RconError> {
+    let (id, kind, body) = read_packet(stream).await?;
+    if id != REQUEST_ID || kind != SERVERDATA_RESPONSE_VALUE {
+        return Err(RconError::Malformed("unexpected response packet"));
+    }
+
+    Ok(body)
+}
+
+async fn send(stream: &mut TcpStream, kind: i32, body: &str) -> Result<(), RconError> {
+    stream.write_all(&packet(REQUEST_ID, kind, body)).await?;
+    Ok(())
+}
+
+/// Little-endian size, id and type followed by the body and two nulls
+fn packet(id: i32, kind: i32, body: &str) -> Vec<u8> {
+    let size = 4 + 4 + body.len() + 2;
+
+    let mut packet = Vec::with_capacity(4 + size);
+    packet.extend_from_slice(&(size as i32).to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+    packet
+}
+
+async fn read_packet(stream: &mut TcpStream) -> Result<(i32, i32, String), RconError> {
+    let size = stream.read_i32_le().await?;
+    if !(10..=MAX_PACKET_LEN).contains(&size) {
+        return Err(RconError::Malformed("unreasonable packet size"));
+    }
+
+    let mut packet = vec![0; size as usize];
+    stream.read_exact(&mut packet).await?;
+
+    let id = i32::from_le_bytes([packet[0], packet[1], packet[2], packet[3]]);
+    let kind = i32::from_le_bytes([packet[4], packet[5], packet[6], packet[7]]);
+
+    // the body is null terminated, followed by an empty string
+    let body = &packet[8..];
+    let end = body.iter().position(|b| *b == 0).unwrap_or(body.len());
+    let body = String::from_utf8_lossy(&body[..end]).into_owned();
+
+    Ok((id, kind, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Fake server that accepts `password` and answers every command with
+    /// `responses`, one packet each
+    async fn fake_server(password: &'static str, responses: Vec<&'static str>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let (id, _, attempt) = read_packet(&mut stream).await.unwrap();
+            let id = if attempt == password { id } else { -1 };
+            stream
+                .write_all(&packet(id, SERVERDATA_RESPONSE_VALUE, ""))
+                .await
+                .unwrap();
+            stream
+                .write_all(&packet(id, SERVERDATA_AUTH_RESPONSE, ""))
+                .await
+                .unwrap();
+
+            let (id, _, _) = read_packet(&mut stream).await.unwrap();
+            for response in responses {
+                stream
+                    .write_all(&packet(id, SERVERDATA_RESPONSE_VALUE, response))
+                    .await
+                    .unwrap();
+            }
+
+            // hold the connection open like a real server
+            let _ = read_packet(&mut stream).await;
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn runs_commands_after_logging_in() {
+        let port = fake_server("hunter2", vec!["Saved the game"]).await;
+
+        let output = execute("127.0.0.1", port, "hunter2", "save-all", RCON_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(output, "Saved the game");
+    }
+
+    #[tokio::test]
+    async fn joins_fragmented_responses() {
+        let port = fake_server("hunter2", vec!["Players: ", "tony, pete"]).await;
+
+        let output = execute("127.0.0.1", port, "hunter2", "players", RCON_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(output, "Players: tony, pete");
+    }
+
+    #[tokio::test]
+    async fn wrong_passwords_are_rejected() {
+        let port = fake_server("hunter2", vec![]).await;
+
+        let result = execute("127.0.0.1", port, "aids", "save-all", RCON_TIMEOUT).await;
+
+        assert!(matches!(result, Err(RconError::AuthFailed)));
+    }
+}
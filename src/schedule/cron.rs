@@ -0,0 +1,257 @@
+//! Five field cron expressions, e.g. `0 5 * * *` for 05:00 every day
+//!
+//! Fields are minute, hour, day of month, month and day of week, each taking
+//! `*`, numbers, ranges (`1-5`), lists (`1,3`) and steps (`*/15`). Days of the
+//! week are 0-7 with both 0 and 7 as Sunday, or names like `tue`.
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+use serde::Deserialize;
+
+use std::fmt;
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead to look for the next run before giving up, e.g. `0 0 31 2 *`
+const SEARCH_LIMIT_DAYS: i64 = 366 * 5;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Standard cron matches either day field when both are restricted
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "\"{expression}\" needs 5 fields: minute hour day month weekday"
+            ));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, "weekday")?;
+        // 7 is another way of writing sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Cron {
+            expression: fields.join(" "),
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    /// Whether the schedule fires during this minute
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        bit(self.minutes, time.minute()) && bit(self.hours, time.hour()) && self.matches_day(time)
+    }
+
+    /// The first minute after `after` that the schedule fires
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(SEARCH_LIMIT_DAYS);
+
+        let mut time = start;
+        while time < limit {
+            if !self.matches_day(time) {
+                time = (time.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: NaiveDateTime) -> bool {
+        if !bit(self.months, time.month()) {
+            return false;
+        }
+
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        Cron::parse(&expression)
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parse one field into a bitset of the values it allows
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut set = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid {name} step \"{step}\""))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start, name)?, value(end, name)?),
+                // `5/10` means from 5 to the end in steps of 10
+                None if step > 1 => (value(range, name)?, max),
+                None => {
+                    let value = value(range, name)?;
+                    (value, value)
+                }
+            },
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("{name} \"{part}\" must be within {min}-{max}"));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
+
+fn value(text: &str, name: &str) -> Result<u32, String> {
+    if let Some(day) = DAY_NAMES
+        .iter()
+        .position(|day| text.eq_ignore_ascii_case(day))
+        .filter(|_| name == "weekday")
+    {
+        return Ok(day as u32);
+    }
+
+    text.parse()
+        .map_err(|_| format!("invalid {name} \"{text}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn daily_schedules_run_tomorrow_once_passed() {
+        let cron = Cron::parse("0 5 * * *").unwrap();
+
+        assert_eq!(
+            cron.next_after(at(2025, 3, 1, 4, 0)),
+            Some(at(2025, 3, 1, 5, 0))
+        );
+        assert_eq!(
+            cron.next_after(at(2025, 3, 1, 5, 0)),
+            Some(at(2025, 3, 2, 5, 0))
+        );
+    }
+
+    #[test]
+    fn weekdays_accept_names_and_numbers() {
+        let by_name = Cron::parse("0 4 * * tue").unwrap();
+        let by_number = Cron::parse("0 4 * * 2").unwrap();
+
+        // 2025-03-01 is a saturday
+        let tuesday = at(2025, 3, 4, 4, 0);
+        assert_eq!(by_name.next_after(at(2025, 3, 1, 0, 0)), Some(tuesday));
+        assert_eq!(by_number.next_after(at(2025, 3, 1, 0, 0)), Some(tuesday));
+    }
+
+    #[test]
+    fn sunday_is_zero_or_seven() {
+        let cron = Cron::parse("30 6 * * 7").unwrap();
+
+        assert!(cron.matches(at(2025, 3, 2, 6, 30)));
+    }
+
+    #[test]
+    fn steps_ranges_and_lists() {
+        let cron = Cron::parse("*/15 9-17 * * 1-5").unwrap();
+
+        assert!(cron.matches(at(2025, 3, 3, 9, 45)));
+        assert!(!cron.matches(at(2025, 3, 3, 9, 50)));
+        assert!(!cron.matches(at(2025, 3, 1, 9, 45)));
+
+        let cron = Cron::parse("0 0,12 1 * *").unwrap();
+        assert!(cron.matches(at(2025, 3, 1, 12, 0)));
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_set() {
+        // the 1st of the month, or any monday
+        let cron = Cron::parse("0 0 1 * mon").unwrap();
+
+        assert!(cron.matches(at(2025, 3, 1, 0, 0)));
+        assert!(cron.matches(at(2025, 3, 3, 0, 0)));
+        assert!(!cron.matches(at(2025, 3, 4, 0, 0)));
+    }
+
+    #[test]
+    fn impossible_dates_never_run() {
+        let cron = Cron::parse("0 0 31 2 *").unwrap();
+
+        assert_eq!(cron.next_after(at(2025, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn bad_expressions_are_rejected() {
+        for expression in [
+            "0 5 * *",
+            "60 * * * *",
+            "0 24 * * *",
+            "*/0 * * * *",
+            "0 5 * * fun",
+        ] {
+            assert!(Cron::parse(expression).is_err(), "{expression}");
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Recurring operations on game servers, e.g. restarting skyvaults every night
+mod cron;
+
+pub use cron::Cron;
+
+use crate::config::{Config, ScheduledAction};
+use crate::store;
+
+use std::sync::{LazyLock, Mutex};
+
+/// Every schedule, starting with the ones from the config
+static SCHEDULES: LazyLock<Mutex<Schedules>> = LazyLock::new(|| Mutex::new(Schedules::default()));
+
+#[derive(Default)]
+struct Schedules {
+    next_id: u32,
+    entries: Vec<Schedule>,
+}
+
+/// An operation to run on a game server whenever `cron` matches
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// Short id for `/schedule remove`
+    pub id: u32,
+    pub game: String,
+    pub action: ScheduledAction,
+    pub cron: Cron,
+    /// Declared in the config, so it can not be removed with `/schedule`
+    pub from_config: bool,
+}
+
+/// Why a schedule could not be removed
+pub enum RemoveError {
+    NotFound,
+    FromConfig(Schedule),
+}
+
+/// Load the schedules declared for every enabled server, then the ones
+/// added with `/schedule` before the bot last restarted
+///
+/// Ids are handed out fresh every time, so stored schedules are renumbered
+/// to match
+pub fn init(config: &Config) {
+    for server in config.enabled_servers() {
+        for schedule in &server.schedules {
+            insert(&server.name, schedule.action, schedule.cron.clone(), true);
+        }
+    }
+
+    let Some(store) = store::get() else {
+        return;
+    };
+
+    let stored = match store.schedules() {
+        Ok(stored) => stored,
+        Err(e) => {
+            eprintln!("Failed to load stored schedules: {e}");
+            return;
+        }
+    };
+
+    let mut kept = Vec::new();
+    for schedule in stored {
+        if config
+            .enabled_servers()
+            .any(|server| server.name == schedule.game)
+        {
+            kept.push(insert(
+                &schedule.game,
+                schedule.action,
+                schedule.cron,
+                false,
+            ));
+        } else {
+            eprintln!(
+                "Dropping the stored schedule for {}, it is not enabled",
+                schedule.game
+            );
+        }
+    }
+
+    store::record("schedules", |store| store.replace_schedules(&kept));
+}
+
+/// A snapshot of every schedule, ordered by id
+pub fn all() -> Vec<Schedule> {
+    SCHEDULES.lock().unwrap().entries.clone()
+}
+
+/// Add a schedule and keep it across restarts, returning it with its new id
+pub fn add(game: &str, action: ScheduledAction, cron: Cron) -> Schedule {
+    let schedule = insert(game, action, cron, false);
+    store::record("schedule", |store| store.add_schedule(&schedule));
+    schedule
+}
+
+fn insert(game: &str, action: ScheduledAction, cron: Cron, from_config: bool) -> Schedule {
+    let mut schedules = SCHEDULES.lock().unwrap();
+    schedules.next_id += 1;
+
+    let schedule = Schedule {
+        id: schedules.next_id,
+        game: game.to_string(),
+        action,
+        cron,
+        from_config,
+    };
+    schedules.entries.push(schedule.clone());
+    schedule
+}
+
+/// Remove a schedule added with `/schedule`, returning what was removed
+pub fn remove(id: u32) -> Result<Schedule, RemoveError> {
+    let mut schedules = SCHEDULES.lock().unwrap();
+    let index = schedules
+        .entries
+        .iter()
+        .position(|s| s.id == id)
+        .ok_or(RemoveError::NotFound)?;
+
+    if schedules.entries[index].from_config {
+        return Err(RemoveError::FromConfig(schedules.entries[index].clone()));
+    }
+
+    let schedule = schedules.entries.remove(index);
+    store::record("schedule removal", |store| store.remove_schedule(id));
+    Ok(schedule)
+}
@@ -0,0 +1,479 @@
+//! SQLite file that remembers what happened across bot restarts
+//!
+//! Recording is best effort: failures are logged and never get in the way of
+//! managing the servers. Before [init] is called (e.g. in tests) every
+//! function quietly does nothing.
+use crate::config::ScheduledAction;
+use crate::games::{OperationResult, ServerStatus};
+use crate::schedule::{Cron, Schedule};
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable to override the location of the database
+const ENV_DATABASE_PATH: &str = "MULTIPLAYER_BOT_DB";
+
+/// Default location of the database, relative to the working directory
+const DEFAULT_DATABASE_PATH: &str = "multiplayer_bot.db";
+
+/// Schema changes in order, the database's `user_version` is how many ran
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE operations (
+        id          INTEGER PRIMARY KEY,
+        game        TEXT NOT NULL,
+        verb        TEXT NOT NULL,
+        actor       TEXT NOT NULL,
+        outcome     TEXT NOT NULL,
+        success     INTEGER NOT NULL,
+        started_at  INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL
+    );
+    CREATE INDEX operations_game ON operations (game, started_at);
+
+    CREATE TABLE transitions (
+        id     INTEGER PRIMARY KEY,
+        game   TEXT NOT NULL,
+        status TEXT NOT NULL,
+        at     INTEGER NOT NULL
+    );
+    CREATE INDEX transitions_game ON transitions (game, at);
+
+    CREATE TABLE player_samples (
+        game        TEXT NOT NULL,
+        players     INTEGER NOT NULL,
+        max_players INTEGER NOT NULL,
+        at          INTEGER NOT NULL
+    );
+    CREATE INDEX player_samples_game ON player_samples (game, at);
+
+    CREATE TABLE schedules (
+        id     INTEGER PRIMARY KEY,
+        game   TEXT NOT NULL,
+        action TEXT NOT NULL,
+        cron   TEXT NOT NULL
+    );
+",
+    "
+    CREATE TABLE bot_state (
+        key   TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+",
+];
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+/// A finished operation, as shown by `/history`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationRecord {
+    pub verb: String,
+    pub actor: String,
+    pub outcome: String,
+    pub success: bool,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+}
+
+/// Everything `/history` shows about a server
+#[derive(Debug, Default)]
+pub struct History {
+    /// Newest first
+    pub operations: Vec<OperationRecord>,
+    pub last_crash: Option<SystemTime>,
+    /// Most players seen at once within the sample window
+    pub peak_players: Option<u32>,
+}
+
+pub struct Store {
+    connection: Mutex<Connection>,
+}
+
+impl Store {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::migrate(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::migrate(Connection::open_in_memory()?)
+    }
+
+    fn migrate(mut connection: Connection) -> rusqlite::Result<Self> {
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", i + 1)?;
+            transaction.commit()?;
+        }
+
+        Ok(Store {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub fn record_operation(
+        &self,
+        game: &str,
+        verb: &str,
+        actor: &str,
+        result: &OperationResult,
+        started_at: SystemTime,
+        duration: Duration,
+    ) -> rusqlite::Result<()> {
+        let (outcome, success) = match result {
+            Ok(outcome) => (outcome.to_string(), true),
+            Err(error) => (error.to_string(), false),
+        };
+
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO operations (game, verb, actor, outcome, success, started_at, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                game,
+                verb,
+                actor,
+                outcome,
+                success,
+                epoch(started_at),
+                duration.as_millis() as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_transition(
+        &self,
+        game: &str,
+        status: &ServerStatus,
+        at: SystemTime,
+    ) -> rusqlite::Result<()> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO transitions (game, status, at) VALUES (?1, ?2, ?3)",
+            params![game, status.to_string(), epoch(at)],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_players(
+        &self,
+        game: &str,
+        players: u32,
+        max_players: u32,
+        at: SystemTime,
+    ) -> rusqlite::Result<()> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO player_samples (game, players, max_players, at) VALUES (?1, ?2, ?3, ?4)",
+            params![game, players, max_players, epoch(at)],
+        )?;
+        Ok(())
+    }
+
+    /// The latest `limit` operations, the last crash and the peak player
+    /// count since `since`
+    pub fn history(
+        &self,
+        game: &str,
+        limit: usize,
+        since: SystemTime,
+    ) -> rusqlite::Result<History> {
+        let connection = self.connection.lock().unwrap();
+
+        let operations = connection
+            .prepare(
+                "SELECT verb, actor, outcome, success, started_at, duration_ms FROM operations
+                 WHERE game = ?1 ORDER BY started_at DESC, id DESC LIMIT ?2",
+            )?
+            .query_map(params![game, limit as i64], |row| {
+                Ok(OperationRecord {
+                    verb: row.get(0)?,
+                    actor: row.get(1)?,
+                    outcome: row.get(2)?,
+                    success: row.get(3)?,
+                    started_at: from_epoch(row.get(4)?),
+                    duration: Duration::from_millis(row.get::<_, i64>(5)?.max(0) as u64),
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let last_crash = connection
+            .query_row(
+                "SELECT MAX(at) FROM transitions WHERE game = ?1 AND status = ?2",
+                params![game, ServerStatus::Crashed.to_string()],
+                |row| row.get::<_, Option<i64>>(0),
+            )?
+            .map(from_epoch);
+
+        let peak_players = connection.query_row(
+            "SELECT MAX(players) FROM player_samples WHERE game = ?1 AND at >= ?2",
+            params![game, epoch(since)],
+            |row| row.get(0),
+        )?;
+
+        Ok(History {
+            operations,
+            last_crash,
+            peak_players,
+        })
+    }
+
+    /// Schedules added through `/schedule`
+    pub fn schedules(&self) -> rusqlite::Result<Vec<Schedule>> {
+        let connection = self.connection.lock().unwrap();
+        let rows = connection
+            .prepare("SELECT id, game, action, cron FROM schedules ORDER BY id")?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // anything that no longer parses is skipped rather than failing startup
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, game, action, cron)| {
+                Some(Schedule {
+                    id,
+                    game,
+                    action: ScheduledAction::from_verb(&action)?,
+                    cron: Cron::parse(&cron).ok()?,
+                    from_config: false,
+                })
+            })
+            .collect())
+    }
+
+    pub fn add_schedule(&self, schedule: &Schedule) -> rusqlite::Result<()> {
+        insert_schedule(&self.connection.lock().unwrap(), schedule)
+    }
+
+    /// Swap every stored schedule for `schedules`, e.g. after renumbering
+    pub fn replace_schedules(&self, schedules: &[Schedule]) -> rusqlite::Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        transaction.execute("DELETE FROM schedules", [])?;
+        for schedule in schedules {
+            insert_schedule(&transaction, schedule)?;
+        }
+        transaction.commit()
+    }
+
+    pub fn remove_schedule(&self, id: u32) -> rusqlite::Result<()> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM schedules WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Something the bot needs to remember about itself, e.g. message ids
+    pub fn state(&self, key: &str) -> rusqlite::Result<Option<String>> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT value FROM bot_state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn set_state(&self, key: &str, value: &str) -> rusqlite::Result<()> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO bot_state (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
+
+/// Open the database at `MULTIPLAYER_BOT_DB`, or `multiplayer_bot.db` by default
+pub fn init() -> rusqlite::Result<()> {
+    let path = env::var(ENV_DATABASE_PATH).unwrap_or_else(|_| DEFAULT_DATABASE_PATH.to_string());
+    let store = Store::open(&path)?;
+
+    if STORE.set(store).is_err() {
+        eprintln!("The store was already initialised, ignoring {path}");
+    }
+    Ok(())
+}
+
+/// The shared store, if it has been opened
+pub fn get() -> Option<&'static Store> {
+    STORE.get()
+}
+
+/// Run a write against the store, logging rather than returning failures
+pub fn record(what: &str, write: impl FnOnce(&Store) -> rusqlite::Result<()>) {
+    if let Some(store) = get() {
+        if let Err(e) = write(store) {
+            eprintln!("Failed to record {what}: {e}");
+        }
+    }
+}
+
+fn insert_schedule(connection: &Connection, schedule: &Schedule) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT OR REPLACE INTO schedules (id, game, action, cron) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            schedule.id,
+            schedule.game,
+            schedule.action.verb(),
+            schedule.cron.to_string()
+        ],
+    )?;
+    Ok(())
+}
+
+fn epoch(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn from_epoch(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::{GameServerError, OperationOutcome};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn history_is_newest_first_with_crashes_and_peaks() {
+        let store = Store::open_in_memory().unwrap();
+        let minute = Duration::from_secs(60);
+
+        store
+            .record_operation(
+                "hytale",
+                "start",
+                "tony",
+                &Ok(OperationOutcome::Started),
+                at(100),
+                minute,
+            )
+            .unwrap();
+        store
+            .record_operation(
+                "hytale",
+                "update",
+                "pete",
+                &Err(GameServerError::NotSupported("has no backups set up")),
+                at(200),
+                minute,
+            )
+            .unwrap();
+        store
+            .record_operation(
+                "7days",
+                "stop",
+                "sam",
+                &Ok(OperationOutcome::Stopped),
+                at(300),
+                minute,
+            )
+            .unwrap();
+        store
+            .record_transition("hytale", &ServerStatus::Crashed, at(150))
+            .unwrap();
+        store.record_players("hytale", 3, 8, at(50)).unwrap();
+        store.record_players("hytale", 2, 8, at(250)).unwrap();
+
+        let history = store.history("hytale", 10, at(100)).unwrap();
+
+        let verbs: Vec<_> = history.operations.iter().map(|o| o.verb.as_str()).collect();
+        assert_eq!(verbs, ["update", "start"]);
+        assert!(!history.operations[0].success);
+        assert_eq!(history.operations[1].outcome, "Started");
+        assert_eq!(history.operations[1].duration, minute);
+        assert_eq!(history.last_crash, Some(at(150)));
+        assert_eq!(history.peak_players, Some(2));
+    }
+
+    fn schedule(id: u32, game: &str, action: ScheduledAction) -> Schedule {
+        Schedule {
+            id,
+            game: game.to_string(),
+            action,
+            cron: Cron::parse("0 5 * * *").unwrap(),
+            from_config: false,
+        }
+    }
+
+    #[test]
+    fn schedules_round_trip() {
+        let store = Store::open_in_memory().unwrap();
+
+        store
+            .add_schedule(&schedule(3, "skyvaults", ScheduledAction::Restart))
+            .unwrap();
+        store
+            .add_schedule(&schedule(4, "zomboid", ScheduledAction::Update))
+            .unwrap();
+        store.remove_schedule(3).unwrap();
+
+        let schedules = store.schedules().unwrap();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].id, 4);
+        assert_eq!(schedules[0].action, ScheduledAction::Update);
+        assert_eq!(schedules[0].cron.to_string(), "0 5 * * *");
+    }
+
+    #[test]
+    fn replacing_schedules_renumbers_them() {
+        let store = Store::open_in_memory().unwrap();
+        store
+            .add_schedule(&schedule(7, "skyvaults", ScheduledAction::Restart))
+            .unwrap();
+
+        store
+            .replace_schedules(&[schedule(2, "skyvaults", ScheduledAction::Restart)])
+            .unwrap();
+
+        let ids: Vec<_> = store.schedules().unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(ids, [2]);
+    }
+
+    #[test]
+    fn state_overwrites() {
+        let store = Store::open_in_memory().unwrap();
+
+        assert_eq!(store.state("board").unwrap(), None);
+        store.set_state("board", "1").unwrap();
+        store.set_state("board", "2").unwrap();
+        assert_eq!(store.state("board").unwrap().as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn migrations_only_run_once() {
+        let path = std::env::temp_dir().join(format!("multiplayer_bot_{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        Store::open(path)
+            .unwrap()
+            .add_schedule(&schedule(1, "zomboid", ScheduledAction::Restart))
+            .unwrap();
+        let reopened = Store::open(path).unwrap();
+
+        assert_eq!(reopened.schedules().unwrap().len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
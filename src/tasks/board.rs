@@ -0,0 +1,195 @@
+//! A pinned board in its own channel showing every server, edited whenever
+//! one changes state or its player count changes
+use crate::commands::list::{self, CARDS_PER_MESSAGE, Card};
+use crate::games::{self, GameServer, StatusReport};
+use crate::store;
+
+use serenity::builder::{CreateActionRow, CreateEmbed, CreateMessage, EditMessage};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId};
+use tokio::time::MissedTickBehavior;
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the servers are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Statuses younger than this are reused, so the board mostly rides on the
+/// checks the other tasks already make
+const MAX_STATUS_AGE: Duration = Duration::from_secs(60);
+
+/// Where the board's message ids are kept, so restarts reuse them
+const STATE_KEY: &str = "board_messages";
+
+/// Keep the board up to date, forever
+pub async fn run(http: Arc<Http>, channel: ChannelId) {
+    let mut board = Board {
+        channel,
+        messages: load_messages(),
+    };
+    let mut shown: Option<Vec<String>> = None;
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        let mut keys = Vec::new();
+        let mut cards = Vec::new();
+        for server in games::all() {
+            let report = server.recent_status(MAX_STATUS_AGE).await;
+            keys.push(change_key(&**server, &report));
+            cards.push(list::card(&**server, &report));
+        }
+
+        if shown.as_ref() == Some(&keys) {
+            continue;
+        }
+
+        if board.show(&http, cards).await {
+            shown = Some(keys);
+        }
+    }
+}
+
+/// What has to differ for the board to be edited, leaving out uptime so it
+/// is not edited every minute
+fn change_key(server: &dyn GameServer, report: &StatusReport) -> String {
+    let players = report
+        .info
+        .as_ref()
+        .map(|info| info.player_summary())
+        .unwrap_or_default();
+    format!("{} {} {players}", server.name(), report.status)
+}
+
+/// The board's messages, each holding up to [CARDS_PER_MESSAGE] servers
+struct Board {
+    channel: ChannelId,
+    messages: Vec<MessageId>,
+}
+
+impl Board {
+    /// Edit the existing messages, or post new ones if they are gone or
+    /// there are a different number of them. Returns false on failure.
+    async fn show(&mut self, http: &Http, cards: Vec<Card>) -> bool {
+        let pages = paginate(cards);
+
+        if pages.len() == self.messages.len() && self.edit(http, &pages).await {
+            return true;
+        }
+
+        self.replace(http, pages).await
+    }
+
+    async fn edit(&self, http: &Http, pages: &[Page]) -> bool {
+        for (message, page) in self.messages.iter().zip(pages) {
+            let edit = EditMessage::new()
+                .content(&page.content)
+                .embeds(page.embeds.clone())
+                .components(page.buttons.clone());
+
+            if let Err(e) = self.channel.edit_message(http, *message, edit).await {
+                eprintln!("Failed to edit the server board, posting it again: {e}");
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn replace(&mut self, http: &Http, pages: Vec<Page>) -> bool {
+        for message in self.messages.drain(..) {
+            let _ = self.channel.delete_message(http, message).await;
+        }
+
+        for page in pages {
+            let message = CreateMessage::new()
+                .content(page.content)
+                .embeds(page.embeds)
+                .components(page.buttons);
+
+            match self.channel.send_message(http, message).await {
+                Ok(message) => {
+                    if let Err(e) = message.pin(http).await {
+                        eprintln!("Failed to pin the server board: {e}");
+                    }
+                    self.messages.push(message.id);
+                }
+                Err(e) => {
+                    eprintln!("Failed to post the server board: {e}");
+                    break;
+                }
+            }
+        }
+
+        save_messages(&self.messages);
+        !self.messages.is_empty()
+    }
+}
+
+/// One message worth of servers
+struct Page {
+    content: String,
+    embeds: Vec<CreateEmbed>,
+    buttons: Vec<CreateActionRow>,
+}
+
+fn paginate(cards: Vec<Card>) -> Vec<Page> {
+    let updated = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let mut pages = Vec::new();
+    let mut cards = cards.into_iter().peekable();
+    while cards.peek().is_some() {
+        let (embeds, buttons) = cards
+            .by_ref()
+            .take(CARDS_PER_MESSAGE)
+            .map(|card| (card.embed, card.buttons))
+            .unzip();
+
+        // only the first message gets a heading
+        let content = if pages.is_empty() {
+            format!("## Brumders servers\nLast change <t:{updated}:R>")
+        } else {
+            String::new()
+        };
+
+        pages.push(Page {
+            content,
+            embeds,
+            buttons,
+        });
+    }
+    pages
+}
+
+fn load_messages() -> Vec<MessageId> {
+    let Some(store) = store::get() else {
+        return Vec::new();
+    };
+
+    match store.state(STATE_KEY) {
+        Ok(Some(ids)) => ids
+            .split(',')
+            .filter_map(|id| id.parse().ok())
+            .filter(|id| *id != 0)
+            .map(MessageId::new)
+            .collect(),
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            eprintln!("Failed to load the server board message ids: {e}");
+            Vec::new()
+        }
+    }
+}
+
+fn save_messages(messages: &[MessageId]) {
+    let ids: Vec<String> = messages.iter().map(|id| id.get().to_string()).collect();
+    store::record("server board", |store| {
+        store.set_state(STATE_KEY, &ids.join(","))
+    });
+}
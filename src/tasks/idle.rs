@@ -0,0 +1,178 @@
+//! Stops servers that have sat empty for longer than their `idle_timeout`
+use crate::commands;
+use crate::games::{self, ServerStatus, format_duration};
+use crate::tasks::notify;
+
+use serenity::http::Http;
+use tokio::time::MissedTickBehavior;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often player counts are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long before stopping a server the warning goes out
+const WARNING_LEAD: Duration = Duration::from_secs(5 * 60);
+
+/// Poll every server with an idle timeout, forever
+pub async fn run(http: Arc<Http>) {
+    let mut tracker = IdleTracker::default();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        for server in games::all().iter() {
+            let Some(timeout) = server.idle_timeout() else {
+                continue;
+            };
+
+            // only a server that is up and answering can be called empty
+            let report = server.status().await;
+            let players = match (&report.status, &report.info) {
+                (ServerStatus::Running, Some(info)) => Some(info.players),
+                _ => None,
+            };
+
+            let name = server.name();
+            match tracker.observe(name, players, Instant::now(), timeout) {
+                IdleAction::Nothing => {}
+                IdleAction::Warn { remaining } => {
+                    let message = format!(
+                        "Nobody is on the {name} server, it will be stopped in {} unless someone joins",
+                        format_duration(remaining)
+                    );
+                    notify(&http, &message).await;
+                }
+                IdleAction::Stop { idle } => {
+                    let message = format!(
+                        "Stopping the {name} server after {} with no players",
+                        format_duration(idle)
+                    );
+                    notify(&http, &message).await;
+
+                    let message =
+                        commands::run_operation(&**server, "stop", "idle shutdown", server.stop())
+                            .await;
+                    notify(&http, &message).await;
+                }
+            }
+        }
+    }
+}
+
+/// What to do about a server after checking its players
+#[derive(Debug, PartialEq, Eq)]
+enum IdleAction {
+    Nothing,
+    Warn { remaining: Duration },
+    Stop { idle: Duration },
+}
+
+/// When each server was first seen empty, and whether anyone was warned
+#[derive(Default)]
+struct IdleTracker {
+    empty: HashMap<String, Empty>,
+}
+
+struct Empty {
+    since: Instant,
+    warned: bool,
+}
+
+impl IdleTracker {
+    /// Record a player count, `None` meaning the server is not running
+    fn observe(
+        &mut self,
+        name: &str,
+        players: Option<u32>,
+        now: Instant,
+        timeout: Duration,
+    ) -> IdleAction {
+        if players != Some(0) {
+            self.empty.remove(name);
+            return IdleAction::Nothing;
+        }
+
+        let empty = self.empty.entry(name.to_string()).or_insert(Empty {
+            since: now,
+            warned: false,
+        });
+
+        let idle = now.duration_since(empty.since);
+        if idle >= timeout {
+            self.empty.remove(name);
+            return IdleAction::Stop { idle };
+        }
+
+        let remaining = timeout - idle;
+        if !empty.warned && remaining <= WARNING_LEAD {
+            empty.warned = true;
+            return IdleAction::Warn { remaining };
+        }
+
+        IdleAction::Nothing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+    fn minutes(start: Instant, minutes: u64) -> Instant {
+        start + Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn warns_once_then_stops_empty_servers() {
+        let mut tracker = IdleTracker::default();
+        let start = Instant::now();
+
+        let actions: Vec<_> = [0, 10, 25, 26, 30]
+            .into_iter()
+            .map(|m| tracker.observe("satisfactory", Some(0), minutes(start, m), TIMEOUT))
+            .collect();
+
+        assert_eq!(
+            actions,
+            [
+                IdleAction::Nothing,
+                IdleAction::Nothing,
+                IdleAction::Warn {
+                    remaining: Duration::from_secs(5 * 60)
+                },
+                IdleAction::Nothing,
+                IdleAction::Stop { idle: TIMEOUT },
+            ]
+        );
+    }
+
+    #[test]
+    fn players_joining_resets_the_clock() {
+        let mut tracker = IdleTracker::default();
+        let start = Instant::now();
+
+        tracker.observe("satisfactory", Some(0), start, TIMEOUT);
+        tracker.observe("satisfactory", Some(2), minutes(start, 20), TIMEOUT);
+        tracker.observe("satisfactory", Some(0), minutes(start, 21), TIMEOUT);
+
+        let action = tracker.observe("satisfactory", Some(0), minutes(start, 31), TIMEOUT);
+        assert_eq!(action, IdleAction::Nothing);
+    }
+
+    #[test]
+    fn stopped_servers_are_never_idle() {
+        let mut tracker = IdleTracker::default();
+        let start = Instant::now();
+
+        for m in [0, 30, 60] {
+            let action = tracker.observe("satisfactory", None, minutes(start, m), TIMEOUT);
+            assert_eq!(action, IdleAction::Nothing);
+        }
+    }
+}
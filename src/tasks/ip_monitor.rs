@@ -0,0 +1,27 @@
+//! Watches the public IP so players hear about a new one before they find
+//! their favourites no longer connect
+use crate::ip;
+
+use serenity::http::Http;
+use tokio::time::MissedTickBehavior;
+
+use std::sync::Arc;
+
+/// Keep the cached IP fresh, forever
+pub async fn run(http: Arc<Http>) {
+    let mut interval = tokio::time::interval(ip::ttl());
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        if let Some(change) = ip::refresh().await {
+            println!("Public IP changed from {} to {}", change.old, change.new);
+            super::notify(
+                &http,
+                &format!("New server IP: {} — update your favourites", change.new),
+            )
+            .await;
+        }
+    }
+}
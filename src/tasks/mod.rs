@@ -0,0 +1,55 @@
+//! Background work that runs for as long as the bot is connected
+mod board;
+mod idle;
+mod ip_monitor;
+mod presence;
+mod scheduler;
+mod update_check;
+mod watchdog;
+
+use crate::config::Config;
+use crate::tokens;
+
+use serenity::http::Http;
+use serenity::prelude::Context;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `ready` fires again after every reconnect, but the tasks only start once
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Spawn every background task, ignoring repeat calls
+pub fn start(ctx: &Context, config: &Config) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    println!("Starting background tasks");
+    tokio::spawn(idle::run(ctx.http.clone()));
+    tokio::spawn(scheduler::run(ctx.http.clone()));
+    tokio::spawn(presence::run(ctx.clone()));
+    tokio::spawn(ip_monitor::run(ctx.http.clone()));
+
+    if config.watchdog.enabled {
+        tokio::spawn(watchdog::run(ctx.http.clone(), config.watchdog.clone()));
+    }
+
+    if let Some(cron) = &config.update_check.cron {
+        tokio::spawn(update_check::run(ctx.http.clone(), cron.clone()));
+    }
+
+    if let Some(channel) = *tokens::BOARD_CHANNEL_ID {
+        tokio::spawn(board::run(ctx.http.clone(), channel));
+    }
+}
+
+/// Post to the notification channel, or only log it if there isn't one
+pub async fn notify(http: &Http, message: &str) {
+    println!("{message}");
+
+    if let Some(channel) = *tokens::NOTIFY_CHANNEL_ID {
+        if let Err(e) = channel.say(http, message).await {
+            eprintln!("Failed to post notification: {e}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Shows what is running in the bot's discord presence, e.g.
+//! "Playing skyvaults (3), 7days (1)"
+use crate::games::{self, ServerStatus};
+
+use serenity::gateway::ActivityData;
+use serenity::prelude::Context;
+use tokio::time::MissedTickBehavior;
+
+use std::time::Duration;
+
+/// How often the presence is refreshed
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Keep the presence up to date, forever
+pub async fn run(ctx: Context) {
+    let mut shown = None;
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        // only ever the cached statuses, which the board, the watchdog, the
+        // idle checks and commands keep fresh, so this never runs anything
+        // on the host. Servers nobody has looked at yet are left out.
+        let mut running = Vec::new();
+        for server in games::all() {
+            let Some(report) = server.cached_status() else {
+                continue;
+            };
+            if report.status == ServerStatus::Running {
+                let players = report.info.map(|info| info.players);
+                running.push((server.name().to_string(), players));
+            }
+        }
+
+        let text = describe(&running);
+        if shown.as_ref() == Some(&text) {
+            continue;
+        }
+
+        let activity = match &text {
+            Some(playing) => ActivityData::playing(playing),
+            None => ActivityData::custom("All servers idle"),
+        };
+        ctx.set_activity(Some(activity));
+        shown = Some(text);
+    }
+}
+
+/// e.g. "skyvaults (3), 7days" for servers without a query, or nothing
+/// when no servers are running
+fn describe(running: &[(String, Option<u32>)]) -> Option<String> {
+    if running.is_empty() {
+        return None;
+    }
+
+    let servers: Vec<String> = running
+        .iter()
+        .map(|(name, players)| match players {
+            Some(players) => format!("{name} ({players})"),
+            None => name.clone(),
+        })
+        .collect();
+
+    Some(servers.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_running_servers_with_players() {
+        let running = [
+            ("skyvaults".to_string(), Some(3)),
+            ("7days".to_string(), Some(1)),
+            ("hytale".to_string(), None),
+        ];
+
+        assert_eq!(
+            describe(&running).as_deref(),
+            Some("skyvaults (3), 7days (1), hytale")
+        );
+    }
+
+    #[test]
+    fn nothing_running_is_idle() {
+        assert_eq!(describe(&[]), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! Runs scheduled operations, warning players in-game beforehand
+use crate::commands;
+use crate::config::ScheduledAction;
+use crate::games::{self, GameServer, GameServerError};
+use crate::schedule::{self, Schedule};
+use crate::tasks::notify;
+
+use chrono::{Duration, Local, NaiveDateTime, Timelike};
+use serenity::http::Http;
+
+use std::sync::Arc;
+
+/// Minutes before a scheduled operation that players are warned
+const WARNINGS: [i64; 2] = [10, 1];
+
+/// Check the schedules at the start of every minute, forever
+pub async fn run(http: Arc<Http>) {
+    let mut last_minute = None;
+
+    loop {
+        let now = Local::now().naive_local();
+        let minute = now
+            .with_second(0)
+            .and_then(|m| m.with_nanosecond(0))
+            .unwrap_or(now);
+
+        if last_minute != Some(minute) {
+            last_minute = Some(minute);
+            for schedule in schedule::all() {
+                check(&http, schedule, minute);
+            }
+        }
+
+        // wake just after the next minute starts
+        let until_next = 60 - u64::from(now.second());
+        tokio::time::sleep(std::time::Duration::from_secs(until_next)).await;
+    }
+}
+
+/// Warn or run a schedule if it is due, without holding up the other schedules
+fn check(http: &Arc<Http>, schedule: Schedule, minute: NaiveDateTime) {
+    let Some(server) = games::get_game_server(&schedule.game) else {
+        return;
+    };
+
+    for lead in WARNINGS {
+        if schedule.cron.matches(minute + Duration::minutes(lead)) {
+            tokio::spawn(warn(server, schedule.action, lead));
+        }
+    }
+
+    if schedule.cron.matches(minute) {
+        tokio::spawn(execute(http.clone(), server, schedule.action));
+    }
+}
+
+async fn warn(server: &'static dyn GameServer, action: ScheduledAction, lead: i64) {
+    if !server.status().await.status.is_up() {
+        return;
+    }
+
+    let plural = if lead == 1 { "" } else { "s" };
+    let message = format!(
+        "Scheduled server {} in {lead} minute{plural}",
+        action.verb()
+    );
+
+    match server.broadcast(&message).await {
+        Ok(()) | Err(GameServerError::NotSupported(_)) => {}
+        Err(error) => eprintln!("{} broadcast failed: {}", server.name(), error.details()),
+    }
+}
+
+async fn execute(http: Arc<Http>, server: &'static dyn GameServer, action: ScheduledAction) {
+    let name = server.name();
+
+    // a restart would otherwise bring up a server someone stopped on purpose
+    if action == ScheduledAction::Restart && !server.status().await.status.is_up() {
+        println!("Skipping the scheduled restart of {name}, it is not running");
+        return;
+    }
+
+    notify(
+        &http,
+        &format!("Running the scheduled {} of {name}", action.verb()),
+    )
+    .await;
+
+    let operation = async {
+        match action {
+            ScheduledAction::Start => server.start().await,
+            ScheduledAction::Stop => server.stop().await,
+            ScheduledAction::Restart => server.restart().await,
+            ScheduledAction::Update => server.update().await,
+        }
+    };
+    let message = commands::run_operation(server, action.verb(), "schedule", operation).await;
+    notify(&http, &message).await;
+}
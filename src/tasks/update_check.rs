@@ -0,0 +1,59 @@
+//! Looks for pending updates on a timetable and posts the table whenever a
+//! new one turns up
+use crate::commands::checkupdates;
+use crate::schedule::Cron;
+use crate::store;
+use crate::tasks::notify;
+
+use chrono::Local;
+use serenity::http::Http;
+
+use std::sync::Arc;
+
+/// Where the updates already announced are kept, so restarts stay quiet
+const STATE_KEY: &str = "announced_updates";
+
+/// Check whenever `cron` matches, forever
+pub async fn run(http: Arc<Http>, cron: Cron) {
+    loop {
+        let now = Local::now().naive_local();
+        let Some(next) = cron.next_after(now) else {
+            eprintln!("The update check schedule never runs, stopping it");
+            return;
+        };
+        tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+
+        let rows = checkupdates::check_all().await;
+        let pending: Vec<String> = rows.iter().filter_map(|row| row.pending_key()).collect();
+
+        let announced = load_announced();
+        if pending.iter().any(|key| !announced.contains(key)) {
+            notify(&http, &checkupdates::table(&rows)).await;
+        }
+
+        // forget updates once installed, so a repeat of the same version
+        // after a rollback is announced again
+        store::record("announced updates", |store| {
+            store.set_state(STATE_KEY, &pending.join(","))
+        });
+    }
+}
+
+fn load_announced() -> Vec<String> {
+    let Some(store) = store::get() else {
+        return Vec::new();
+    };
+
+    match store.state(STATE_KEY) {
+        Ok(keys) => keys
+            .unwrap_or_default()
+            .split(',')
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to load the announced updates: {e}");
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Restarts servers that crash while they are supposed to be running
+use crate::commands;
+use crate::config::WatchdogConfig;
+use crate::games::{self, ServerStatus, format_duration};
+use crate::tasks::notify;
+use crate::tokens;
+
+use serenity::http::Http;
+use tokio::time::MissedTickBehavior;
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often every server is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Check every server against what it was last asked to do, forever
+pub async fn run(http: Arc<Http>, config: WatchdogConfig) {
+    let mut supervisor = Supervisor::new(&config);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        for server in games::all().iter() {
+            let expected = server.expected_status();
+            if expected.is_none() {
+                continue;
+            }
+
+            let status = server.status().await.status;
+            let name = server.name();
+
+            match supervisor.observe(name, expected, &status, Instant::now()) {
+                WatchdogAction::Nothing => {}
+                WatchdogAction::Restart { attempt, max } => {
+                    let message = format!("{name} crashed, restarting (attempt {attempt}/{max})");
+                    notify(&http, &message).await;
+
+                    let message =
+                        commands::run_operation(&**server, "start", "watchdog", server.start())
+                            .await;
+                    notify(&http, &message).await;
+                }
+                WatchdogAction::GiveUp { restarts, window } => {
+                    let mention = tokens::ADMIN_ROLE_ID
+                        .map(|admin| format!("<@&{}> ", admin.get()))
+                        .unwrap_or_default();
+                    let message = format!(
+                        "{mention}{name} crashed again after {restarts} restarts in {}, \
+                        giving up until someone looks at it",
+                        format_duration(window)
+                    );
+                    notify(&http, &message).await;
+                }
+            }
+        }
+    }
+}
+
+/// What to do about a server after checking it
+#[derive(Debug, PartialEq, Eq)]
+enum WatchdogAction {
+    Nothing,
+    Restart { attempt: u32, max: u32 },
+    GiveUp { restarts: u32, window: Duration },
+}
+
+/// Restart history of every server that has crashed
+struct Supervisor {
+    max_restarts: u32,
+    window: Duration,
+    backoff: Duration,
+    crashes: HashMap<String, Crashes>,
+}
+
+#[derive(Default)]
+struct Crashes {
+    /// When each restart within the window was attempted
+    restarts: VecDeque<Instant>,
+    /// No restarts before this, so a crash loop does not hammer the host
+    next_attempt: Option<Instant>,
+    /// Set once the admins have been told, until the server comes back
+    gave_up: bool,
+}
+
+impl Supervisor {
+    fn new(config: &WatchdogConfig) -> Self {
+        Supervisor {
+            max_restarts: config.max_restarts,
+            window: Duration::from_secs(config.window * 60),
+            backoff: Duration::from_secs(config.backoff * 60),
+            crashes: HashMap::new(),
+        }
+    }
+
+    fn observe(
+        &mut self,
+        name: &str,
+        expected: Option<ServerStatus>,
+        status: &ServerStatus,
+        now: Instant,
+    ) -> WatchdogAction {
+        // stopping a server on purpose forgets its history
+        if expected != Some(ServerStatus::Running) {
+            self.crashes.remove(name);
+            return WatchdogAction::Nothing;
+        }
+
+        let crashes = self.crashes.entry(name.to_string()).or_default();
+
+        if status.is_up() {
+            crashes.gave_up = false;
+        }
+
+        if !matches!(status, ServerStatus::Crashed | ServerStatus::Stopped) {
+            return WatchdogAction::Nothing;
+        }
+
+        while crashes
+            .restarts
+            .front()
+            .is_some_and(|restart| now.duration_since(*restart) > self.window)
+        {
+            crashes.restarts.pop_front();
+        }
+
+        if crashes.gave_up || crashes.next_attempt.is_some_and(|next| now < next) {
+            return WatchdogAction::Nothing;
+        }
+
+        let restarts = crashes.restarts.len() as u32;
+        if restarts >= self.max_restarts {
+            crashes.gave_up = true;
+            return WatchdogAction::GiveUp {
+                restarts,
+                window: self.window,
+            };
+        }
+
+        crashes.restarts.push_back(now);
+        crashes.next_attempt = Some(now + self.backoff * 2u32.pow(restarts));
+
+        WatchdogAction::Restart {
+            attempt: restarts + 1,
+            max: self.max_restarts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNNING: Option<ServerStatus> = Some(ServerStatus::Running);
+
+    fn supervisor() -> Supervisor {
+        Supervisor::new(&WatchdogConfig::default())
+    }
+
+    fn minutes(start: Instant, minutes: u64) -> Instant {
+        start + Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn restarts_with_backoff_then_gives_up() {
+        let mut supervisor = supervisor();
+        let start = Instant::now();
+
+        let actions: Vec<_> = [0, 0, 1, 2, 3, 7, 8]
+            .into_iter()
+            .map(|m| {
+                let now = minutes(start, m);
+                supervisor.observe("hytale", RUNNING, &ServerStatus::Crashed, now)
+            })
+            .collect();
+
+        assert_eq!(
+            actions,
+            [
+                WatchdogAction::Restart { attempt: 1, max: 3 },
+                WatchdogAction::Nothing,
+                WatchdogAction::Restart { attempt: 2, max: 3 },
+                WatchdogAction::Nothing,
+                WatchdogAction::Restart { attempt: 3, max: 3 },
+                WatchdogAction::GiveUp {
+                    restarts: 3,
+                    window: Duration::from_secs(60 * 60)
+                },
+                WatchdogAction::Nothing,
+            ]
+        );
+    }
+
+    #[test]
+    fn old_restarts_fall_out_of_the_window() {
+        let mut supervisor = supervisor();
+        let start = Instant::now();
+
+        for m in [0, 1, 3] {
+            supervisor.observe("hytale", RUNNING, &ServerStatus::Crashed, minutes(start, m));
+        }
+
+        let action = supervisor.observe(
+            "hytale",
+            RUNNING,
+            &ServerStatus::Crashed,
+            minutes(start, 62),
+        );
+        assert_eq!(action, WatchdogAction::Restart { attempt: 2, max: 3 });
+    }
+
+    #[test]
+    fn stopped_on_purpose_is_left_alone() {
+        let mut supervisor = supervisor();
+        let now = Instant::now();
+
+        let expected = Some(ServerStatus::Stopped);
+        let action = supervisor.observe("hytale", expected, &ServerStatus::Stopped, now);
+
+        assert_eq!(action, WatchdogAction::Nothing);
+    }
+
+    #[test]
+    fn healthy_servers_are_left_alone() {
+        let mut supervisor = supervisor();
+        let now = Instant::now();
+
+        for status in [
+            ServerStatus::Running,
+            ServerStatus::Starting,
+            ServerStatus::Updating,
+        ] {
+            assert_eq!(
+                supervisor.observe("hytale", RUNNING, &status, now),
+                WatchdogAction::Nothing
+            );
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use serenity::model::id::{GuildId, RoleId};
+use serenity::model::id::{ChannelId, GuildId, RoleId};
 use std::env;
 use std::sync::LazyLock;
 
@@ -9,6 +9,14 @@ const ENV_BOT_TOKEN: &str = "TEST_BOT";
 const ENV_GUILD_ID: &str = "TEST_GUILD_ID";
 #[cfg(debug_assertions)]
 const ENV_TRUSTED_ROLE_ID: &str = "TEST_TRUSTED_ROLE";
+#[cfg(debug_assertions)]
+const ENV_ADMIN_ROLE_ID: &str = "TEST_ADMIN_ROLE";
+#[cfg(debug_assertions)]
+const ENV_NOTIFY_CHANNEL_ID: &str = "TEST_NOTIFY_CHANNEL";
+#[cfg(debug_assertions)]
+const ENV_AUDIT_CHANNEL_ID: &str = "TEST_AUDIT_CHANNEL";
+#[cfg(debug_assertions)]
+const ENV_BOARD_CHANNEL_ID: &str = "TEST_BOARD_CHANNEL";
 
 // Swap to brumders tokens/IDs for release
 #[cfg(not(debug_assertions))]
@@ -17,6 +25,14 @@ const ENV_BOT_TOKEN: &str = "MUTLIPLAYER_BOT";
 const ENV_GUILD_ID: &str = "BRUMDERS_GUILD_ID";
 #[cfg(not(debug_assertions))]
 const ENV_TRUSTED_ROLE_ID: &str = "BRUMDERS_TRUSTED_ROLE";
+#[cfg(not(debug_assertions))]
+const ENV_ADMIN_ROLE_ID: &str = "BRUMDERS_ADMIN_ROLE";
+#[cfg(not(debug_assertions))]
+const ENV_NOTIFY_CHANNEL_ID: &str = "BRUMDERS_NOTIFY_CHANNEL";
+#[cfg(not(debug_assertions))]
+const ENV_AUDIT_CHANNEL_ID: &str = "BRUMDERS_AUDIT_CHANNEL";
+#[cfg(not(debug_assertions))]
+const ENV_BOARD_CHANNEL_ID: &str = "BRUMDERS_BOARD_CHANNEL";
 
 /// The developer discord token for the bot
 pub static BOT_TOKEN: LazyLock<String> =
@@ -41,3 +57,37 @@ pub static TRUSTED_ROLE_ID: LazyLock<RoleId> = LazyLock::new(|| {
             .expect("TRUSTED_ROLE_ID must be an integer"),
     )
 });
+
+/// ID of the admin role allowed to run in-game console commands, optional so
+/// older setups keep working with the admin-only commands turned off
+pub static ADMIN_ROLE_ID: LazyLock<Option<RoleId>> = LazyLock::new(|| {
+    let id = env::var(ENV_ADMIN_ROLE_ID).ok()?;
+    Some(RoleId::new(
+        id.parse().expect("ADMIN_ROLE_ID must be an integer"),
+    ))
+});
+
+/// ID of the channel background tasks post to, which is optional since the
+/// bot works fine without notifications
+pub static NOTIFY_CHANNEL_ID: LazyLock<Option<ChannelId>> = LazyLock::new(|| {
+    let id = env::var(ENV_NOTIFY_CHANNEL_ID).ok()?;
+    Some(ChannelId::new(
+        id.parse().expect("NOTIFY_CHANNEL_ID must be an integer"),
+    ))
+});
+
+/// ID of the channel every command is logged to, also optional
+pub static AUDIT_CHANNEL_ID: LazyLock<Option<ChannelId>> = LazyLock::new(|| {
+    let id = env::var(ENV_AUDIT_CHANNEL_ID).ok()?;
+    Some(ChannelId::new(
+        id.parse().expect("AUDIT_CHANNEL_ID must be an integer"),
+    ))
+});
+
+/// ID of the channel holding the pinned server board, also optional
+pub static BOARD_CHANNEL_ID: LazyLock<Option<ChannelId>> = LazyLock::new(|| {
+    let id = env::var(ENV_BOARD_CHANNEL_ID).ok()?;
+    Some(ChannelId::new(
+        id.parse().expect("BOARD_CHANNEL_ID must be an integer"),
+    ))
+});